@@ -3,6 +3,15 @@ pub const CONTRACT_NAME: &str = "crates.io:cw721-base";
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const BASE_URI: &str = "ipfs://QmRiLKmhizpnwqpHGeiJnL4G6fsPAxdEdCiDkuJpt7xHPH/";
 
+// sale
+pub const MINT_DENOM: &str = "uusd";
+pub const MINT_PRICE: u128 = 100000;
+pub const MAX_TOKENS: u64 = 100;
+
+pub const TRANSFER_HOOK_REPLY_ID: u64 = 1;
+
+pub const DEFAULT_HISTORY_RETENTION: u32 = 20;
+
 // addresses
 
 pub const ADDR_TEAM: &str = "terra109rgfl6x8v6k29dtfpc4kxq9tl08lly73wgjzg";
@@ -0,0 +1,1703 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use cosmwasm_std::testing::{
+    mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    attr, coin, coins, from_binary, from_slice, Addr, BankMsg, Binary, Coin, ContractResult,
+    CosmosMsg, Decimal, Deps, DepsMut, Empty, Env, Order, OwnedDeps, Pair, Querier, QuerierResult,
+    QuerierWrapper, QueryRequest, Reply, ReplyOn, Response, Storage, SystemError, SystemResult,
+    Uint128, WasmMsg, WasmQuery,
+};
+use cw0::Duration;
+use cw721::{Expiration, TokensResponse};
+use cw_storage_plus::U64Key;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::constants::*;
+use crate::error::ContractError;
+use crate::msg::*;
+use crate::state::*;
+use crate::state2::*;
+
+const MINTER: &str = "minter";
+
+/// Storage shared with the querier, so `mint` can query the contract itself
+#[derive(Clone, Default)]
+struct SharedStorage(Rc<RefCell<MockStorage>>);
+
+impl Storage for SharedStorage {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.0.borrow().get(key)
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Pair> + 'a> {
+        let pairs: Vec<Pair> = self.0.borrow().range(start, end, order).collect();
+        Box::new(pairs.into_iter())
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.0.borrow_mut().set(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.0.borrow_mut().remove(key)
+    }
+}
+
+/// Answers smart queries to any contract with this contract's queries
+struct ContractQuerier {
+    storage: SharedStorage,
+    base: MockQuerier,
+}
+
+impl Querier for ContractQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<Empty> = match from_slice(bin_request) {
+            Ok(request) => request,
+            Err(err) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: err.to_string(),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        match request {
+            QueryRequest::Wasm(WasmQuery::Smart { msg, .. }) => {
+                let deps = Deps {
+                    storage: &self.storage,
+                    api: &MockApi::default(),
+                    querier: QuerierWrapper::new(&self.base),
+                };
+                let res = from_binary(&msg)
+                    .and_then(|msg| Cw721ExtendedContract::default().query(deps, mock_env(), msg));
+                SystemResult::Ok(ContractResult::from(res))
+            }
+            _ => self.base.raw_query(bin_request),
+        }
+    }
+}
+
+type TestDeps = OwnedDeps<SharedStorage, MockApi, ContractQuerier>;
+
+fn mock_deps() -> TestDeps {
+    let storage = SharedStorage::default();
+    OwnedDeps {
+        storage: storage.clone(),
+        api: MockApi::default(),
+        querier: ContractQuerier {
+            storage,
+            base: MockQuerier::new(&[]),
+        },
+    }
+}
+
+fn metadata(name: &str) -> Metadata {
+    Metadata {
+        name: Some(name.to_string()),
+        ..Metadata::default()
+    }
+}
+
+/// Instantiates the contract and uploads the metadata of tokens 1..=tokens
+fn setup(tokens: u64) -> TestDeps {
+    let mut deps = mock_deps();
+    let msg = InstantiateMsg {
+        name: "Animas".to_string(),
+        symbol: "ANIMA".to_string(),
+        minter: MINTER.to_string(),
+        voters: vec![Voter {
+            addr: MINTER.to_string(),
+            weight: 1,
+        }],
+        required_weight: 1,
+        max_voting_period: Duration::Time(3600),
+    };
+    let contract = Cw721ExtendedContract::default();
+    contract
+        .instantiate(deps.as_mut(), mock_env(), mock_info(MINTER, &[]), msg)
+        .unwrap();
+    for id in 1..=tokens {
+        upload(deps.as_mut(), id, metadata(&format!("Anima #{}", id)));
+    }
+    deps
+}
+
+fn upload(deps: DepsMut, id: u64, meta: Metadata) {
+    let msg = ExecuteMsg::AddExtension(AddExtensionMsg {
+        token_id: id.to_string(),
+        extension: Some(meta),
+    });
+    execute(deps, mock_env(), MINTER, &[], msg).unwrap();
+}
+
+fn execute(
+    mut deps: DepsMut,
+    env: Env,
+    sender: &str,
+    funds: &[Coin],
+    msg: ExecuteMsg<Extension>,
+) -> Result<Response, ContractError> {
+    // like on chain, a failed execution leaves no state changes behind
+    let backup: Vec<Pair> = deps.storage.range(None, None, Order::Ascending).collect();
+    let res =
+        Cw721ExtendedContract::default().execute(deps.branch(), env, mock_info(sender, funds), msg);
+    if res.is_err() {
+        let keys: Vec<Vec<u8>> = deps
+            .storage
+            .range(None, None, Order::Ascending)
+            .map(|(k, _)| k)
+            .collect();
+        for key in keys {
+            deps.storage.remove(&key);
+        }
+        for (key, value) in backup {
+            deps.storage.set(&key, &value);
+        }
+    }
+    res
+}
+
+fn query<T: DeserializeOwned>(deps: Deps, env: Env, msg: QueryMsg) -> T {
+    from_binary(
+        &Cw721ExtendedContract::default()
+            .query(deps, env, msg)
+            .unwrap(),
+    )
+    .unwrap()
+}
+
+/// Mints the next token to `owner`, paying the fixed price
+fn mint(deps: DepsMut, owner: &str) -> Response {
+    let msg = ExecuteMsg::Mint(MintMsg {
+        token_num: String::new(),
+        owner: owner.to_string(),
+        token_uri: None,
+        extension: None,
+        voucher: None,
+        referrer: None,
+    });
+    execute(deps, mock_env(), owner, &coins(MINT_PRICE, MINT_DENOM), msg).unwrap()
+}
+
+fn bank_sends(res: &Response) -> Vec<(String, Vec<Coin>)> {
+    res.messages
+        .iter()
+        .filter_map(|sub| match &sub.msg {
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                Some((to_address.clone(), amount.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Transfer hook notifications of a response, with how their failure is handled
+fn hook_calls(res: &Response) -> Vec<(String, ReplyOn, u64, TransferHookMsg)> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    enum HookExecuteMsg {
+        TransferHook(TransferHookMsg),
+    }
+    res.messages
+        .iter()
+        .filter_map(|sub| match &sub.msg {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr, msg, ..
+            }) => match from_binary(msg) {
+                Ok(HookExecuteMsg::TransferHook(hook)) => {
+                    Some((contract_addr.clone(), sub.reply_on.clone(), sub.id, hook))
+                }
+                Err(_) => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn owner_of(deps: Deps, token_id: &str) -> Addr {
+    Cw721ExtendedContract::default()
+        .tokens
+        .load(deps.storage, token_id)
+        .unwrap()
+        .owner
+}
+
+fn set_hook(deps: DepsMut, contract: &str, failure: Option<HookFailure>) {
+    let msg = ExecuteMsg::SetTransferHook {
+        contract: contract.to_string(),
+        failure,
+    };
+    execute(deps, mock_env(), MINTER, &[], msg).unwrap();
+}
+
+fn transfer(deps: DepsMut, sender: &str, recipient: &str, token_id: &str) -> Response {
+    let msg = ExecuteMsg::TransferNft {
+        recipient: recipient.to_string(),
+        token_id: token_id.to_string(),
+    };
+    execute(deps, mock_env(), sender, &[], msg).unwrap()
+}
+
+#[test]
+fn withdraw_leaves_escrow() {
+    let mut deps = setup(1);
+    mint(deps.as_mut(), "seller");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        "seller",
+        &[],
+        ExecuteMsg::CreateAuction {
+            token_id: "1".to_string(),
+            denom: "uluna".to_string(),
+            reserve_price: Uint128::new(100),
+            min_increment: Uint128::new(10),
+            duration: 3600,
+            extension: 0,
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        "bidder",
+        &coins(500, "uluna"),
+        ExecuteMsg::PlaceBid { auction_id: 1 },
+    )
+    .unwrap();
+    // the bid and 1000uluna of sales
+    deps.querier
+        .base
+        .update_balance(mock_env().contract.address, coins(1500, "uluna"));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        "bidder",
+        &[],
+        ExecuteMsg::Withdraw {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotMinter {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        MINTER,
+        &[],
+        ExecuteMsg::Withdraw {},
+    )
+    .unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![
+            (ADDR_TEAM.to_string(), coins(300, "uluna")),
+            (ADDR_PRO.to_string(), coins(140, "uluna")),
+            (ADDR_TREAS.to_string(), coins(560, "uluna")),
+        ]
+    );
+
+    // settling pays the escrowed bid out, after which nothing is held back
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(3600);
+    execute(
+        deps.as_mut(),
+        env,
+        "anyone",
+        &[],
+        ExecuteMsg::SettleAuction { auction_id: 1 },
+    )
+    .unwrap();
+    let contract = Cw721ExtendedContract::default();
+    assert_eq!(
+        contract.escrow.may_load(&deps.storage, "uluna").unwrap(),
+        None
+    );
+    let owner: cw721::OwnerOfResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::OwnerOf {
+            token_id: "1".to_string(),
+            include_expired: None,
+        },
+    );
+    assert_eq!(owner.owner, Addr::unchecked("bidder").to_string());
+}
+
+#[test]
+fn offers_are_escrowed() {
+    let mut deps = setup(1);
+    mint(deps.as_mut(), "owner");
+    let make_offer = |denom: &str| ExecuteMsg::MakeOffer {
+        target: OfferTarget::Collection {},
+        denom: denom.to_string(),
+        expires: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        "bidder",
+        &coins(200, "uluna"),
+        make_offer("uluna"),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        "bidder",
+        &coins(300, "uluna"),
+        make_offer("uluna"),
+    )
+    .unwrap();
+    let contract = Cw721ExtendedContract::default();
+    assert_eq!(
+        contract.escrow.load(&deps.storage, "uluna").unwrap(),
+        Uint128::new(500)
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        "bidder",
+        &[],
+        ExecuteMsg::CancelOffer { offer_id: 1 },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        "owner",
+        &[],
+        ExecuteMsg::AcceptOffer {
+            offer_id: 2,
+            token_id: "1".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        contract.escrow.may_load(&deps.storage, "uluna").unwrap(),
+        None
+    );
+}
+
+#[test]
+fn swap_funds_are_escrowed() {
+    let mut deps = setup(2);
+    mint(deps.as_mut(), "alice");
+    mint(deps.as_mut(), "bob");
+    let propose = ExecuteMsg::ProposeSwap {
+        counterparty: "bob".to_string(),
+        offered_tokens: vec!["1".to_string()],
+        requested_tokens: vec!["2".to_string()],
+        requested_funds: vec![],
+        expires: None,
+    };
+    let sweetener = [coin(60, "uluna"), coin(40, "uluna"), coin(10, "uusd")];
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        "alice",
+        &sweetener,
+        propose.clone(),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        "alice",
+        &coins(5, "uluna"),
+        propose,
+    )
+    .unwrap();
+    let contract = Cw721ExtendedContract::default();
+    assert_eq!(
+        contract.escrow.load(&deps.storage, "uluna").unwrap(),
+        Uint128::new(105)
+    );
+    assert_eq!(
+        contract.escrow.load(&deps.storage, "uusd").unwrap(),
+        Uint128::new(10)
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        "bob",
+        &[],
+        ExecuteMsg::CancelSwap { swap_id: 1 },
+    )
+    .unwrap();
+    assert_eq!(
+        contract.escrow.load(&deps.storage, "uluna").unwrap(),
+        Uint128::new(5)
+    );
+    assert_eq!(
+        contract.escrow.may_load(&deps.storage, "uusd").unwrap(),
+        None
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        "bob",
+        &[],
+        ExecuteMsg::AcceptSwap { swap_id: 2 },
+    )
+    .unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![("bob".to_string(), coins(5, "uluna"))]
+    );
+    assert_eq!(
+        contract.escrow.may_load(&deps.storage, "uluna").unwrap(),
+        None
+    );
+}
+
+#[test]
+fn rent_is_escrowed() {
+    let mut deps = setup(1);
+    mint(deps.as_mut(), "owner");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        "owner",
+        &[],
+        ExecuteMsg::ListForRent {
+            token_id: "1".to_string(),
+            price: Uint128::new(1000),
+            denom: "uluna".to_string(),
+            duration: 1000,
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        "renter",
+        &coins(1000, "uluna"),
+        ExecuteMsg::Rent {
+            token_id: "1".to_string(),
+        },
+    )
+    .unwrap();
+    let contract = Cw721ExtendedContract::default();
+    assert_eq!(
+        contract.escrow.load(&deps.storage, "uluna").unwrap(),
+        Uint128::new(1000)
+    );
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(250);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        "anyone",
+        &[],
+        ExecuteMsg::ClaimRent {
+            token_id: "1".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![("owner".to_string(), coins(250, "uluna"))]
+    );
+    assert_eq!(
+        contract.escrow.load(&deps.storage, "uluna").unwrap(),
+        Uint128::new(750)
+    );
+
+    // a transfer halfway through settles the rest of the escrow
+    env.block.time = env.block.time.plus_seconds(250);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        "owner",
+        &[],
+        ExecuteMsg::TransferNft {
+            recipient: "buyer".to_string(),
+            token_id: "1".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![
+            ("owner".to_string(), coins(250, "uluna")),
+            ("renter".to_string(), coins(500, "uluna")),
+        ]
+    );
+    assert_eq!(
+        contract.escrow.may_load(&deps.storage, "uluna").unwrap(),
+        None
+    );
+}
+
+fn with_traits(name: &str, traits: &[(&str, &str)]) -> Metadata {
+    Metadata {
+        attributes: Some(
+            traits
+                .iter()
+                .map(|(trait_type, value)| Trait {
+                    trait_type: trait_type.to_string(),
+                    value: value.to_string(),
+                    ..Trait::default()
+                })
+                .collect(),
+        ),
+        ..metadata(name)
+    }
+}
+
+#[test]
+fn rarity_hidden_until_minted() {
+    let mut deps = setup(0);
+    upload(
+        deps.as_mut(),
+        1,
+        with_traits("Anima #1", &[("eyes", "red")]),
+    );
+    upload(
+        deps.as_mut(),
+        2,
+        with_traits("Anima #2", &[("eyes", "blue")]),
+    );
+    upload(
+        deps.as_mut(),
+        3,
+        with_traits("Anima #3", &[("eyes", "blue")]),
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        MINTER,
+        &[],
+        ExecuteMsg::FinalizeUpload {
+            rarity_method: RarityMethod::InverseFrequency,
+        },
+    )
+    .unwrap();
+
+    // nothing is written into the metadata of unminted tokens
+    let contract = Cw721ExtendedContract::default();
+    let stored = contract.tokens.load(&deps.storage, "1").unwrap();
+    let attr = &stored.extension.unwrap().attributes.unwrap()[0];
+    assert_eq!(attr.count, "");
+    assert_eq!(attr.frequency, "");
+
+    mint(deps.as_mut(), "alice");
+    let minted = contract.tokens.load(&deps.storage, "1").unwrap();
+    let attr = &minted.extension.unwrap().attributes.unwrap()[0];
+    assert_eq!(attr.count, "1");
+    assert_eq!(attr.frequency, Decimal::from_ratio(1u64, 3u64).to_string());
+
+    let res: RarityResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Rarity {
+            token_id: "1".to_string(),
+        },
+    );
+    assert_eq!(res.rarity.rank, 1);
+    contract
+        .query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Rarity {
+                token_id: "2".to_string(),
+            },
+        )
+        .unwrap_err();
+
+    let res: RarityRankingResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::RarityRanking {
+            start_after: None,
+            limit: None,
+        },
+    );
+    let ranked: Vec<String> = res.tokens.into_iter().map(|t| t.token_id).collect();
+    assert_eq!(ranked, vec!["1".to_string()]);
+}
+
+fn dutch_auction(env: &Env) -> DutchAuction {
+    DutchAuction {
+        start_time: env.block.time,
+        start_price: Uint128::new(300),
+        floor_price: Uint128::new(100),
+        price_decrement: Uint128::new(100),
+        time_step: 100,
+        rebate: true,
+    }
+}
+
+fn mint_paying(
+    deps: DepsMut,
+    env: Env,
+    owner: &str,
+    amount: u128,
+    referrer: Option<&str>,
+) -> Response {
+    let msg = ExecuteMsg::Mint(MintMsg {
+        token_num: String::new(),
+        owner: owner.to_string(),
+        token_uri: None,
+        extension: None,
+        voucher: None,
+        referrer: referrer.map(String::from),
+    });
+    execute(deps, env, owner, &coins(amount, MINT_DENOM), msg).unwrap()
+}
+
+#[test]
+fn unclaimed_rebate_does_not_block_next_auction() {
+    let mut deps = setup(4);
+    let mut env = mock_env();
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        MINTER,
+        &[],
+        ExecuteMsg::SetDutchAuction {
+            auction: Some(dutch_auction(&env)),
+        },
+    )
+    .unwrap();
+    mint_paying(deps.as_mut(), env.clone(), "alice", 300, None);
+    env.block.time = env.block.time.plus_seconds(200);
+    mint_paying(deps.as_mut(), env.clone(), "bob", 100, None);
+
+    // alice has not claimed her rebate yet
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        MINTER,
+        &[],
+        ExecuteMsg::SetDutchAuction {
+            auction: Some(dutch_auction(&env)),
+        },
+    )
+    .unwrap();
+    let res: DutchAuctionResponse = query(deps.as_ref(), env.clone(), QueryMsg::DutchAuction {});
+    assert_eq!(res.auction_id, 2);
+    assert_eq!(res.clearing_price, None);
+
+    // the new auction's payments are kept apart
+    mint_paying(deps.as_mut(), env.clone(), "carol", 300, None);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        "carol",
+        &[],
+        ExecuteMsg::ClaimRebate { auction_id: None },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DutchAuctionNotEnded {});
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        "carol",
+        &[],
+        ExecuteMsg::ClaimRebate {
+            auction_id: Some(1),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NothingToClaim {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        "alice",
+        &[],
+        ExecuteMsg::ClaimRebate {
+            auction_id: Some(1),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![("alice".to_string(), coins(200, MINT_DENOM))]
+    );
+    let err = execute(
+        deps.as_mut(),
+        env,
+        "alice",
+        &[],
+        ExecuteMsg::ClaimRebate {
+            auction_id: Some(1),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NothingToClaim {});
+}
+
+#[test]
+fn auction_referral_commission_on_clearing_price() {
+    let mut deps = setup(4);
+    let mut env = mock_env();
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        MINTER,
+        &[],
+        ExecuteMsg::SetReferralConfig {
+            config: ReferralConfig {
+                enabled: true,
+                commission_bps: 1000,
+                max_commission: None,
+            },
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        MINTER,
+        &[],
+        ExecuteMsg::SetDutchAuction {
+            auction: Some(dutch_auction(&env)),
+        },
+    )
+    .unwrap();
+    mint_paying(deps.as_mut(), env.clone(), "alice", 300, Some("referrer"));
+
+    // nothing is earned while the clearing price is unknown
+    let stats: ReferralStatsResponse = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::ReferralStats {
+            referrer: "referrer".to_string(),
+        },
+    );
+    assert_eq!(stats.claimable, Uint128::zero());
+    assert_eq!(stats.stats.referrals, 1);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        "referrer",
+        &[],
+        ExecuteMsg::ClaimReferralCommission {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NothingToClaim {});
+
+    env.block.time = env.block.time.plus_seconds(200);
+    mint_paying(deps.as_mut(), env.clone(), "bob", 100, Some("referrer"));
+
+    // both mints clear at 100, the rebate and the commission fit in what was paid
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        "alice",
+        &[],
+        ExecuteMsg::ClaimRebate { auction_id: None },
+    )
+    .unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![("alice".to_string(), coins(200, MINT_DENOM))]
+    );
+    let stats: ReferralStatsResponse = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::ReferralStats {
+            referrer: "referrer".to_string(),
+        },
+    );
+    assert_eq!(stats.claimable, Uint128::new(20));
+    assert_eq!(stats.stats.volume, Uint128::new(200));
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        "referrer",
+        &[],
+        ExecuteMsg::ClaimReferralCommission {},
+    )
+    .unwrap();
+    assert_eq!(
+        bank_sends(&res),
+        vec![("referrer".to_string(), coins(20, MINT_DENOM))]
+    );
+
+    let contract = Cw721ExtendedContract::default();
+    assert_eq!(
+        contract.mint_revenue.load(&deps.storage).unwrap(),
+        Uint128::new(180)
+    );
+    let err = execute(
+        deps.as_mut(),
+        env,
+        "referrer",
+        &[],
+        ExecuteMsg::ClaimReferralCommission {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NothingToClaim {});
+}
+
+#[test]
+fn batch_transfer_is_all_or_nothing() {
+    let mut deps = setup(3);
+    mint(deps.as_mut(), "alice");
+    mint(deps.as_mut(), "bob");
+    mint(deps.as_mut(), "carol");
+    transfer(deps.as_mut(), "bob", "alice", "2");
+    set_hook(deps.as_mut(), "hook", Some(HookFailure::Fail));
+
+    // a token alice does not own, a missing token and a duplicate each fail the whole batch
+    for token_ids in vec![
+        vec!["1", "2", "3"],
+        vec!["1", "2", "9"],
+        vec!["1", "2", "1"],
+    ] {
+        let msg = ExecuteMsg::BatchTransferNft {
+            recipient: "dave".to_string(),
+            token_ids: token_ids.into_iter().map(String::from).collect(),
+        };
+        execute(deps.as_mut(), mock_env(), "alice", &[], msg).unwrap_err();
+        assert_eq!(owner_of(deps.as_ref(), "1"), "alice");
+        assert_eq!(owner_of(deps.as_ref(), "2"), "alice");
+        assert_eq!(owner_of(deps.as_ref(), "3"), "carol");
+    }
+
+    let msg = ExecuteMsg::BatchTransferNft {
+        recipient: "dave".to_string(),
+        token_ids: vec!["1".to_string(), "2".to_string()],
+    };
+    let res = execute(deps.as_mut(), mock_env(), "alice", &[], msg).unwrap();
+    assert_eq!(owner_of(deps.as_ref(), "1"), "dave");
+    assert_eq!(owner_of(deps.as_ref(), "2"), "dave");
+    let transfers: Vec<&str> = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "transfer")
+        .flat_map(|event| event.attributes.iter())
+        .filter(|attr| attr.key == "token_id")
+        .map(|attr| attr.value.as_str())
+        .collect();
+    assert_eq!(transfers, vec!["1", "2"]);
+    let hooks: Vec<String> = hook_calls(&res)
+        .into_iter()
+        .map(|(_, _, _, hook)| hook.token_id)
+        .collect();
+    assert_eq!(hooks, vec!["1".to_string(), "2".to_string()]);
+}
+
+#[test]
+fn batch_send_notifies_once() {
+    let mut deps = setup(2);
+    mint(deps.as_mut(), "alice");
+    mint(deps.as_mut(), "bob");
+    transfer(deps.as_mut(), "bob", "alice", "2");
+    set_hook(deps.as_mut(), "hook", Some(HookFailure::Fail));
+
+    let msg = ExecuteMsg::BatchSendNft {
+        contract: "receiver".to_string(),
+        token_ids: vec!["1".to_string(), "2".to_string()],
+        msg: Binary::from(b"payload".to_vec()),
+    };
+    let res = execute(deps.as_mut(), mock_env(), "alice", &[], msg).unwrap();
+    assert_eq!(owner_of(deps.as_ref(), "1"), "receiver");
+    assert_eq!(owner_of(deps.as_ref(), "2"), "receiver");
+
+    // a single callback for the batch, an event and a hook call per token
+    let callbacks: Vec<&WasmMsg> = res
+        .messages
+        .iter()
+        .filter_map(|sub| match &sub.msg {
+            CosmosMsg::Wasm(wasm @ WasmMsg::Execute { contract_addr, .. })
+                if contract_addr == "receiver" =>
+            {
+                Some(wasm)
+            }
+            _ => None,
+        })
+        .collect();
+    let expected = Cw721BatchReceiveMsg {
+        sender: "alice".to_string(),
+        token_ids: vec!["1".to_string(), "2".to_string()],
+        msg: Binary::from(b"payload".to_vec()),
+    }
+    .into_cosmos_msg::<_, Empty>("receiver")
+    .unwrap();
+    assert_eq!(callbacks.len(), 1);
+    assert_eq!(CosmosMsg::Wasm(callbacks[0].clone()), expected);
+    assert_eq!(
+        res.events.iter().filter(|event| event.ty == "send").count(),
+        2
+    );
+    let hooks: Vec<(String, String)> = hook_calls(&res)
+        .into_iter()
+        .map(|(_, _, _, hook)| (hook.token_id, hook.action))
+        .collect();
+    assert_eq!(
+        hooks,
+        vec![
+            ("1".to_string(), "send".to_string()),
+            ("2".to_string(), "send".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn transfer_hooks_on_mint_transfer_and_burn() {
+    let mut deps = setup(1);
+    set_hook(deps.as_mut(), "strict", Some(HookFailure::Fail));
+    set_hook(deps.as_mut(), "lenient", Some(HookFailure::Ignore));
+
+    // a failing `Fail` hook reverts the execution, an `Ignore` hook replies on error only
+    let hooks = |res: &Response| -> Vec<(String, ReplyOn, u64)> {
+        hook_calls(res)
+            .into_iter()
+            .map(|(contract, reply_on, id, _)| (contract, reply_on, id))
+            .collect()
+    };
+    let expected = vec![
+        (
+            "lenient".to_string(),
+            ReplyOn::Error,
+            TRANSFER_HOOK_REPLY_ID,
+        ),
+        ("strict".to_string(), ReplyOn::Never, 0),
+    ];
+
+    let res = mint(deps.as_mut(), "alice");
+    assert_eq!(hooks(&res), expected);
+    assert_eq!(
+        hook_calls(&res)[0].3,
+        TransferHookMsg {
+            token_id: "1".to_string(),
+            from: None,
+            to: Some("alice".to_string()),
+            action: "mint".to_string(),
+        }
+    );
+
+    let res = transfer(deps.as_mut(), "alice", "bob", "1");
+    assert_eq!(hooks(&res), expected);
+    assert_eq!(
+        hook_calls(&res)[1].3,
+        TransferHookMsg {
+            token_id: "1".to_string(),
+            from: Some("alice".to_string()),
+            to: Some("bob".to_string()),
+            action: "transfer".to_string(),
+        }
+    );
+
+    let msg = ExecuteMsg::Burn {
+        token_id: "1".to_string(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), "bob", &[], msg).unwrap();
+    assert_eq!(hooks(&res), expected);
+    assert_eq!(
+        hook_calls(&res)[0].3,
+        TransferHookMsg {
+            token_id: "1".to_string(),
+            from: Some("bob".to_string()),
+            to: None,
+            action: "burn".to_string(),
+        }
+    );
+
+    // a removed hook is no longer notified
+    set_hook(deps.as_mut(), "strict", None);
+    let res: TransferHooksResponse = query(deps.as_ref(), mock_env(), QueryMsg::TransferHooks {});
+    assert_eq!(
+        res.hooks,
+        vec![(Addr::unchecked("lenient"), HookFailure::Ignore)]
+    );
+}
+
+#[test]
+fn ignored_hook_failure_is_recorded() {
+    let mut deps = setup(0);
+    let contract = Cw721ExtendedContract::default();
+    let res = contract
+        .reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: TRANSFER_HOOK_REPLY_ID,
+                result: ContractResult::Err("hook out of gas".to_string()),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "transfer_hook_failed"),
+            attr("error", "hook out of gas"),
+        ]
+    );
+
+    let err = contract
+        .reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: 42,
+                result: ContractResult::Err("boom".to_string()),
+            },
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::UnknownReplyId { id: 42 });
+}
+
+#[test]
+fn snapshot_records_end_of_block() {
+    let mut deps = setup(2);
+    mint(deps.as_mut(), "alice");
+    mint(deps.as_mut(), "bob");
+    let first = mock_env();
+    execute(
+        deps.as_mut(),
+        first.clone(),
+        MINTER,
+        &[],
+        ExecuteMsg::TakeSnapshot {},
+    )
+    .unwrap();
+
+    let transfer_at = |deps: DepsMut, env: &Env, sender: &str, recipient: &str, token_id: &str| {
+        let msg = ExecuteMsg::TransferNft {
+            recipient: recipient.to_string(),
+            token_id: token_id.to_string(),
+        };
+        execute(deps, env.clone(), sender, &[], msg).unwrap();
+    };
+
+    // changes before and after the snapshot in its block are both part of it
+    let mut second = mock_env();
+    second.block.height += 1;
+    transfer_at(deps.as_mut(), &second, "alice", "carol", "1");
+    execute(
+        deps.as_mut(),
+        second.clone(),
+        MINTER,
+        &[],
+        ExecuteMsg::TakeSnapshot {},
+    )
+    .unwrap();
+    transfer_at(deps.as_mut(), &second, "carol", "dave", "1");
+
+    let mut third = mock_env();
+    third.block.height += 2;
+    transfer_at(deps.as_mut(), &third, "bob", "dave", "2");
+
+    let tokens_of = |deps: Deps, owner: &str, height: u64| -> Vec<String> {
+        let res: TokensResponse = query(
+            deps,
+            mock_env(),
+            QueryMsg::TokensOfAtHeight {
+                owner: owner.to_string(),
+                height,
+                start_after: None,
+                limit: None,
+            },
+        );
+        res.tokens
+    };
+    assert_eq!(
+        tokens_of(deps.as_ref(), "alice", first.block.height),
+        vec!["1"]
+    );
+    assert_eq!(
+        tokens_of(deps.as_ref(), "bob", first.block.height),
+        vec!["2"]
+    );
+    assert!(tokens_of(deps.as_ref(), "carol", second.block.height).is_empty());
+    assert_eq!(
+        tokens_of(deps.as_ref(), "dave", second.block.height),
+        vec!["1"]
+    );
+    assert_eq!(
+        tokens_of(deps.as_ref(), "bob", second.block.height),
+        vec!["2"]
+    );
+
+    let res: OwnerOfAtHeightResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::OwnerOfAtHeight {
+            token_id: "2".to_string(),
+            height: second.block.height,
+        },
+    );
+    assert_eq!(res.owner, Some(Addr::unchecked("bob")));
+
+    // no snapshot was taken in the third block
+    Cw721ExtendedContract::default()
+        .query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::OwnerOfAtHeight {
+                token_id: "2".to_string(),
+                height: third.block.height,
+            },
+        )
+        .unwrap_err();
+}
+
+#[test]
+fn holder_count_follows_ownership() {
+    let mut deps = setup(3);
+    let holders = |deps: Deps| -> (u64, Vec<(String, u64)>) {
+        let stats: CollectionStatsResponse = query(deps, mock_env(), QueryMsg::CollectionStats {});
+        let res: HoldersResponse = query(
+            deps,
+            mock_env(),
+            QueryMsg::Holders {
+                start_after: None,
+                limit: None,
+            },
+        );
+        let list = res
+            .holders
+            .into_iter()
+            .map(|holder| (holder.address.to_string(), holder.count))
+            .collect();
+        (stats.holders, list)
+    };
+    let balance = |deps: Deps, owner: &str| -> u64 {
+        let res: GetBalanceResponse = query(
+            deps,
+            mock_env(),
+            QueryMsg::GetBalance {
+                owner: owner.to_string(),
+            },
+        );
+        res.balance
+    };
+
+    mint(deps.as_mut(), "alice");
+    mint(deps.as_mut(), "bob");
+    mint(deps.as_mut(), "carol");
+    transfer(deps.as_mut(), "bob", "alice", "2");
+    assert_eq!(
+        holders(deps.as_ref()),
+        (2, vec![("alice".to_string(), 2), ("carol".to_string(), 1)])
+    );
+    assert_eq!(balance(deps.as_ref(), "alice"), 2);
+    assert_eq!(balance(deps.as_ref(), "bob"), 0);
+
+    // the last token leaving a wallet drops it
+    transfer(deps.as_mut(), "carol", "dave", "3");
+    transfer(deps.as_mut(), "dave", "alice", "3");
+    assert_eq!(holders(deps.as_ref()), (1, vec![("alice".to_string(), 3)]));
+
+    // a token escrowed by the contract has no holder until it leaves
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        "alice",
+        &[],
+        ExecuteMsg::CreateAuction {
+            token_id: "1".to_string(),
+            denom: "uluna".to_string(),
+            reserve_price: Uint128::new(100),
+            min_increment: Uint128::new(10),
+            duration: 3600,
+            extension: 0,
+        },
+    )
+    .unwrap();
+    assert_eq!(holders(deps.as_ref()), (1, vec![("alice".to_string(), 2)]));
+    assert_eq!(balance(deps.as_ref(), MOCK_CONTRACT_ADDR), 0);
+
+    let burn = |deps: DepsMut, token_id: &str| {
+        let msg = ExecuteMsg::Burn {
+            token_id: token_id.to_string(),
+        };
+        execute(deps, mock_env(), "alice", &[], msg).unwrap();
+    };
+    burn(deps.as_mut(), "2");
+    assert_eq!(holders(deps.as_ref()), (1, vec![("alice".to_string(), 1)]));
+    burn(deps.as_mut(), "3");
+    assert_eq!(holders(deps.as_ref()), (0, vec![]));
+
+    // the wallet limit still counts mints, whatever is held now
+    let msg = ExecuteMsg::Mint(MintMsg {
+        token_num: String::new(),
+        owner: "alice".to_string(),
+        token_uri: None,
+        extension: None,
+        voucher: None,
+        referrer: None,
+    });
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        "alice",
+        &coins(MINT_PRICE, MINT_DENOM),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WalletLimitExceeded {});
+}
+
+fn approve_all(deps: DepsMut, owner: &str, operator: &str, expires: Option<Expiration>) {
+    let msg = ExecuteMsg::ApproveAll {
+        operator: operator.to_string(),
+        expires,
+    };
+    execute(deps, mock_env(), owner, &[], msg).unwrap();
+}
+
+/// Grants to `operator` as (owner, expires), expired ones included
+fn grants(deps: Deps, env: Env, operator: &str) -> Vec<(String, Expiration)> {
+    let res: OperatorGrantsResponse = query(
+        deps,
+        env,
+        QueryMsg::OperatorGrants {
+            operator: operator.to_string(),
+            include_expired: Some(true),
+            start_after: None,
+            limit: None,
+        },
+    );
+    res.grants
+        .into_iter()
+        .map(|grant| (grant.owner, grant.expires))
+        .collect()
+}
+
+#[test]
+fn operator_grants_follow_operators() {
+    let mut deps = setup(0);
+    let contract = Cw721ExtendedContract::default();
+    let never = Expiration::Never {};
+    let later = Expiration::AtHeight(mock_env().block.height + 100);
+    approve_all(deps.as_mut(), "alice", "operator", None);
+    approve_all(deps.as_mut(), "bob", "operator", Some(later));
+    approve_all(deps.as_mut(), "carol", "operator", None);
+    approve_all(deps.as_mut(), "carol", "other", None);
+    assert_eq!(
+        grants(deps.as_ref(), mock_env(), "operator"),
+        vec![
+            ("alice".to_string(), never),
+            ("bob".to_string(), later),
+            ("carol".to_string(), never),
+        ]
+    );
+
+    // a new approval replaces the expiration in both maps
+    approve_all(deps.as_mut(), "alice", "operator", Some(later));
+    assert_eq!(
+        grants(deps.as_ref(), mock_env(), "operator")[0],
+        ("alice".to_string(), later)
+    );
+    let key = (&Addr::unchecked("alice"), &Addr::unchecked("operator"));
+    assert_eq!(contract.operators.load(&deps.storage, key).unwrap(), later);
+
+    let msg = ExecuteMsg::RevokeAll {
+        operator: "operator".to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), "bob", &[], msg).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        "carol",
+        &[],
+        ExecuteMsg::RevokeAllOperators {},
+    )
+    .unwrap();
+    assert_eq!(
+        grants(deps.as_ref(), mock_env(), "operator"),
+        vec![("alice".to_string(), later)]
+    );
+    assert_eq!(grants(deps.as_ref(), mock_env(), "other"), vec![]);
+    for (owner, operator) in [
+        ("bob", "operator"),
+        ("carol", "operator"),
+        ("carol", "other"),
+    ] {
+        let key = (&Addr::unchecked(owner), &Addr::unchecked(operator));
+        assert_eq!(
+            contract.operators.may_load(&deps.storage, key).unwrap(),
+            None
+        );
+    }
+}
+
+#[test]
+fn prune_removes_expired_grants_within_bound() {
+    let mut deps = setup(0);
+    let contract = Cw721ExtendedContract::default();
+    let soon = Expiration::AtHeight(mock_env().block.height + 5);
+    for (owner, expires) in [
+        ("owner1", Some(soon)),
+        ("owner2", None),
+        ("owner3", Some(soon)),
+        ("owner4", Some(soon)),
+        ("owner5", None),
+    ] {
+        approve_all(deps.as_mut(), owner, "operator", expires);
+    }
+
+    let mut env = mock_env();
+    env.block.height += 10;
+    let prune = |deps: DepsMut, start_after: Option<&str>| -> Response {
+        let msg = ExecuteMsg::PruneOperatorGrants {
+            operator: "operator".to_string(),
+            start_after: start_after.map(String::from),
+            limit: Some(2),
+        };
+        execute(deps, env.clone(), "anyone", &[], msg).unwrap()
+    };
+    let attr_value = |res: &Response, key: &str| -> String {
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.clone())
+            .unwrap()
+    };
+
+    // only two grants are scanned, the expired one among them is removed
+    let res = prune(deps.as_mut(), None);
+    assert_eq!(attr_value(&res, "pruned"), "1");
+    assert_eq!(attr_value(&res, "last_owner"), "owner2");
+    let owners: Vec<String> = grants(deps.as_ref(), env.clone(), "operator")
+        .into_iter()
+        .map(|(owner, _)| owner)
+        .collect();
+    assert_eq!(owners, vec!["owner2", "owner3", "owner4", "owner5"]);
+
+    // resuming after the last owner scanned
+    let res = prune(deps.as_mut(), Some("owner2"));
+    assert_eq!(attr_value(&res, "pruned"), "2");
+    assert_eq!(attr_value(&res, "last_owner"), "owner4");
+    let res = prune(deps.as_mut(), Some("owner4"));
+    assert_eq!(attr_value(&res, "pruned"), "0");
+    assert_eq!(
+        grants(deps.as_ref(), env, "operator"),
+        vec![
+            ("owner2".to_string(), Expiration::Never {}),
+            ("owner5".to_string(), Expiration::Never {}),
+        ]
+    );
+    for owner in ["owner1", "owner3", "owner4"] {
+        let key = (&Addr::unchecked(owner), &Addr::unchecked("operator"));
+        assert_eq!(
+            contract.operators.may_load(&deps.storage, key).unwrap(),
+            None
+        );
+    }
+}
+
+#[test]
+fn traits_indexed_from_mint_to_burn() {
+    let mut deps = setup(0);
+    upload(
+        deps.as_mut(),
+        1,
+        with_traits("Anima #1", &[("eyes", "red"), ("hat", "cap")]),
+    );
+    upload(
+        deps.as_mut(),
+        2,
+        with_traits("Anima #2", &[("eyes", "red")]),
+    );
+    upload(
+        deps.as_mut(),
+        3,
+        with_traits("Anima #3", &[("eyes", "blue")]),
+    );
+
+    let by_trait = |deps: Deps, trait_type: &str, value: &str| -> (u64, Vec<String>) {
+        let res: TokensByTraitResponse = query(
+            deps,
+            mock_env(),
+            QueryMsg::TokensByTrait {
+                trait_type: trait_type.to_string(),
+                value: value.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        );
+        (res.count, res.tokens)
+    };
+    let values = |deps: Deps| -> Vec<(String, u64)> {
+        let res: TraitValuesResponse = query(
+            deps,
+            mock_env(),
+            QueryMsg::TraitValues {
+                trait_type: "eyes".to_string(),
+                start_after: None,
+                limit: None,
+            },
+        );
+        res.values
+            .into_iter()
+            .map(|value| (value.value, value.count))
+            .collect()
+    };
+
+    // uploaded metadata is not indexed until minted
+    assert_eq!(by_trait(deps.as_ref(), "eyes", "red"), (0, vec![]));
+    assert_eq!(values(deps.as_ref()), vec![]);
+
+    mint(deps.as_mut(), "alice");
+    mint(deps.as_mut(), "bob");
+    assert_eq!(
+        by_trait(deps.as_ref(), "eyes", "red"),
+        (2, vec!["1".to_string(), "2".to_string()])
+    );
+    assert_eq!(
+        by_trait(deps.as_ref(), "hat", "cap"),
+        (1, vec!["1".to_string()])
+    );
+    assert_eq!(values(deps.as_ref()), vec![("red".to_string(), 2)]);
+
+    let msg = ExecuteMsg::Burn {
+        token_id: "1".to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), "alice", &[], msg).unwrap();
+    assert_eq!(
+        by_trait(deps.as_ref(), "eyes", "red"),
+        (1, vec!["2".to_string()])
+    );
+    assert_eq!(by_trait(deps.as_ref(), "hat", "cap"), (0, vec![]));
+    assert_eq!(values(deps.as_ref()), vec![("red".to_string(), 1)]);
+}
+
+#[test]
+fn locked_token_cannot_move_or_be_listed() {
+    let mut deps = setup(1);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        MINTER,
+        &[],
+        ExecuteMsg::SetTransferLock {
+            token_ids: vec!["1".to_string()],
+            lock: Some(TransferLock { burnable: true }),
+        },
+    )
+    .unwrap();
+    mint(deps.as_mut(), "alice");
+
+    let locked = ContractError::TransferLocked {
+        token_id: "1".to_string(),
+    };
+    for msg in vec![
+        ExecuteMsg::TransferNft {
+            recipient: "bob".to_string(),
+            token_id: "1".to_string(),
+        },
+        ExecuteMsg::SendNft {
+            contract: "market".to_string(),
+            token_id: "1".to_string(),
+            msg: Binary::default(),
+        },
+        ExecuteMsg::Approve {
+            spender: "bob".to_string(),
+            token_id: "1".to_string(),
+            expires: None,
+        },
+        ExecuteMsg::ListNft {
+            token_id: "1".to_string(),
+            price: Uint128::new(100),
+            denom: MINT_DENOM.to_string(),
+            expires: None,
+        },
+    ] {
+        let err = execute(deps.as_mut(), mock_env(), "alice", &[], msg).unwrap_err();
+        assert_eq!(err, locked);
+    }
+    assert_eq!(owner_of(deps.as_ref(), "1"), "alice");
+
+    // the lock allows burning
+    let msg = ExecuteMsg::Burn {
+        token_id: "1".to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), "alice", &[], msg).unwrap();
+}
+
+#[test]
+fn burn_cleans_up_token_state() {
+    let mut deps = setup(0);
+    upload(
+        deps.as_mut(),
+        1,
+        with_traits("Anima #1", &[("eyes", "red")]),
+    );
+    upload(
+        deps.as_mut(),
+        2,
+        with_traits("Anima #2", &[("eyes", "blue")]),
+    );
+    mint(deps.as_mut(), "alice");
+    mint(deps.as_mut(), "bob");
+
+    let mut env = mock_env();
+    let run = |deps: DepsMut, env: &Env, sender: &str, funds: &[Coin], msg| {
+        execute(deps, env.clone(), sender, funds, msg).unwrap()
+    };
+    run(
+        deps.as_mut(),
+        &env,
+        "alice",
+        &[],
+        ExecuteMsg::ListNft {
+            token_id: "1".to_string(),
+            price: Uint128::new(500),
+            denom: "uluna".to_string(),
+            expires: None,
+        },
+    );
+    run(
+        deps.as_mut(),
+        &env,
+        "alice",
+        &[],
+        ExecuteMsg::ListForRent {
+            token_id: "1".to_string(),
+            price: Uint128::new(1000),
+            denom: "uluna".to_string(),
+            duration: 1000,
+        },
+    );
+    run(
+        deps.as_mut(),
+        &env,
+        "carol",
+        &coins(1000, "uluna"),
+        ExecuteMsg::Rent {
+            token_id: "1".to_string(),
+        },
+    );
+    run(
+        deps.as_mut(),
+        &env,
+        "bob",
+        &coins(300, "uluna"),
+        ExecuteMsg::MakeOffer {
+            target: OfferTarget::Token {
+                token_id: "1".to_string(),
+            },
+            denom: "uluna".to_string(),
+            expires: None,
+        },
+    );
+    run(
+        deps.as_mut(),
+        &env,
+        "bob",
+        &coins(50, "uluna"),
+        ExecuteMsg::ProposeSwap {
+            counterparty: "alice".to_string(),
+            offered_tokens: vec!["2".to_string()],
+            requested_tokens: vec!["1".to_string()],
+            requested_funds: vec![],
+            expires: None,
+        },
+    );
+
+    env.block.time = env.block.time.plus_seconds(250);
+    let res = run(
+        deps.as_mut(),
+        &env,
+        "alice",
+        &[],
+        ExecuteMsg::Burn {
+            token_id: "1".to_string(),
+        },
+    );
+    // the rent earned so far, the unused rent, the offer and the swap funds
+    assert_eq!(
+        bank_sends(&res),
+        vec![
+            ("alice".to_string(), coins(250, "uluna")),
+            ("carol".to_string(), coins(750, "uluna")),
+            ("bob".to_string(), coins(300, "uluna")),
+            ("bob".to_string(), coins(50, "uluna")),
+        ]
+    );
+
+    let contract = Cw721ExtendedContract::default();
+    let storage = &deps.storage;
+    assert!(contract.tokens.may_load(storage, "1").unwrap().is_none());
+    assert!(contract.listings.may_load(storage, "1").unwrap().is_none());
+    assert!(contract
+        .rental_listings
+        .may_load(storage, "1")
+        .unwrap()
+        .is_none());
+    assert!(contract.rentals.may_load(storage, "1").unwrap().is_none());
+    assert!(contract
+        .token_users
+        .may_load(storage, "1")
+        .unwrap()
+        .is_none());
+    assert!(contract
+        .offers
+        .may_load(storage, U64Key::new(1))
+        .unwrap()
+        .is_none());
+    assert!(contract
+        .swaps
+        .may_load(storage, U64Key::new(1))
+        .unwrap()
+        .is_none());
+    assert_eq!(contract.escrow.may_load(storage, "uluna").unwrap(), None);
+    let res: TokensByTraitResponse = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::TokensByTrait {
+            trait_type: "eyes".to_string(),
+            value: "red".to_string(),
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!((res.count, res.tokens), (0, vec![]));
+    let stats: CollectionStatsResponse = query(deps.as_ref(), env, QueryMsg::CollectionStats {});
+    assert_eq!((stats.minted, stats.burned), (2, 1));
+}
+
+#[test]
+fn listing_needs_price_and_valid_denom() {
+    let mut deps = setup(1);
+    mint(deps.as_mut(), "alice");
+    let list = |price: u128, denom: &str| ExecuteMsg::ListNft {
+        token_id: "1".to_string(),
+        price: Uint128::new(price),
+        denom: denom.to_string(),
+        expires: None,
+    };
+    for (price, denom) in [
+        (0, "uluna"),
+        (100, ""),
+        (100, "lu"),
+        (100, "1una"),
+        (100, "u luna"),
+    ] {
+        let err = execute(deps.as_mut(), mock_env(), "alice", &[], list(price, denom)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidListing {});
+    }
+    let contract = Cw721ExtendedContract::default();
+    assert!(contract
+        .listings
+        .may_load(&deps.storage, "1")
+        .unwrap()
+        .is_none());
+
+    for denom in [
+        "uluna",
+        "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2",
+    ] {
+        execute(deps.as_mut(), mock_env(), "alice", &[], list(100, denom)).unwrap();
+    }
+}
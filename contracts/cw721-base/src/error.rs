@@ -1,5 +1,5 @@
 use crate::threshold::ThresholdError;
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -16,8 +16,18 @@ pub enum ContractError {
     #[error("Cannot set approval that is already expired")]
     Expired {},
 
-    #[error("Provided Fund Mismatch")]
-    FundMismatch {},
+    #[error("Insufficient funds: expected {expected}{denom}, received {received}{denom}")]
+    InsufficientFunds {
+        denom: String,
+        expected: Uint128,
+        received: Uint128,
+    },
+
+    #[error("Unexpected denom {denom} in funds")]
+    UnexpectedDenom { denom: String },
+
+    #[error("Denom {denom} is listed more than once")]
+    DuplicateDenom { denom: String },
 
     #[error("Wallet balance limit exceeded")]
     WalletLimitExceeded {},
@@ -43,6 +53,180 @@ pub enum ContractError {
     #[error("Not all signed")]
     NotAllSigned {},
 
+    #[error("Invalid dutch auction parameters")]
+    InvalidDutchAuction {},
+
+    #[error("Dutch auction has not started yet")]
+    DutchAuctionNotStarted {},
+
+    #[error("Dutch auction has not ended yet")]
+    DutchAuctionNotEnded {},
+
+    #[error("Dutch auction rebates are disabled")]
+    RebateDisabled {},
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("A signed voucher is required to mint")]
+    VoucherRequired {},
+
+    #[error("Invalid voucher signature")]
+    InvalidSignature {},
+
+    #[error("Voucher has expired")]
+    VoucherExpired {},
+
+    #[error("Voucher has already been used")]
+    VoucherUsed {},
+
+    #[error("Voucher was issued for another recipient")]
+    VoucherRecipientMismatch {},
+
+    #[error("Invalid referral config")]
+    InvalidReferralConfig {},
+
+    #[error("Cannot refer yourself")]
+    SelfReferral {},
+
+    #[error("Mints from contracts are not allowed")]
+    ContractSender {},
+
+    #[error("Mint limit for this address in this block exceeded")]
+    AddressBlockLimitExceeded {},
+
+    #[error("Mint limit for this block exceeded")]
+    BlockLimitExceeded {},
+
+    #[error("Mint cooldown active, retry in {remaining} seconds")]
+    MintCooldown { remaining: u64 },
+
+    #[error("Listing has expired")]
+    ListingExpired {},
+
+    #[error("Cannot buy your own listing")]
+    CannotBuyOwnListing {},
+
+    #[error("Cannot rent your own token")]
+    CannotRentOwnToken {},
+
+    #[error("Cannot accept your own offer")]
+    CannotAcceptOwnOffer {},
+
+    #[error("Auction reserve price, minimum increment and duration must be positive")]
+    InvalidAuction {},
+
+    #[error("Listing price must be positive and its denom valid")]
+    InvalidListing {},
+
+    #[error("History retention must be positive")]
+    InvalidHistoryRetention {},
+
+    #[error("Auction is not open")]
+    AuctionNotOpen {},
+
+    #[error("Auction has ended")]
+    AuctionEnded {},
+
+    #[error("Auction has not ended yet")]
+    AuctionNotEnded {},
+
+    #[error("Auction already has bids")]
+    AuctionHasBids {},
+
+    #[error("Bid too low, at least {min_bid} required")]
+    BidTooLow { min_bid: Uint128 },
+
+    #[error("Offer has expired")]
+    OfferExpired {},
+
+    #[error("Token does not match the offer")]
+    OfferTargetMismatch {},
+
+    #[error("Offer amount cannot be zero")]
+    ZeroOffer {},
+
+    #[error("Swap must exchange at least one token or coin on each side")]
+    EmptySwap {},
+
+    #[error("Token {token_id} is listed more than once")]
+    DuplicateToken { token_id: String },
+
+    #[error("Cannot swap with yourself")]
+    CannotSwapWithSelf {},
+
+    #[error("Swap has expired")]
+    SwapExpired {},
+
+    #[error("Token {token_id} is not owned by {owner}")]
+    TokenNotOwned { token_id: String, owner: String },
+
+    #[error("Staking is disabled")]
+    StakingDisabled {},
+
+    #[error("Token {token_id} is staked")]
+    TokenStaked { token_id: String },
+
+    #[error("Token {token_id} is not staked")]
+    NotStaked { token_id: String },
+
+    #[error("Token {token_id} is locked to its owner")]
+    TransferLocked { token_id: String },
+
+    #[error("Invalid token range")]
+    InvalidSeries {},
+
+    #[error("Token {token_id} is rented out")]
+    RentalActive { token_id: String },
+
+    #[error("No tokens given")]
+    EmptyBatch {},
+
+    #[error("Operator {operator} is not allowed")]
+    OperatorNotAllowed { operator: String },
+
+    #[error("Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("Permit key must be a compressed secp256k1 key")]
+    InvalidPermitKey {},
+
+    #[error("Permit key does not belong to {owner}")]
+    PermitSignerMismatch { owner: String },
+
+    #[error("Permit has expired")]
+    PermitExpired {},
+
+    #[error("Invalid permit nonce, expected {expected}")]
+    InvalidPermitNonce { expected: u64 },
+
+    #[error("Metadata field {field} cannot be empty")]
+    EmptyMetadataField { field: String },
+
+    #[error("Metadata field {field} is not a valid uri: {uri}")]
+    InvalidMetadataUri { field: String, uri: String },
+
+    #[error("Background color must be six hex digits: {color}")]
+    InvalidBackgroundColor { color: String },
+
+    #[error("Trait type {trait_type} appears more than once")]
+    DuplicateTraitType { trait_type: String },
+
+    #[error("Trait {trait_type} has a non numeric {field}")]
+    InvalidTraitNumber { trait_type: String, field: String },
+
+    #[error("Required trait type {trait_type} is missing")]
+    MissingTraitType { trait_type: String },
+
+    #[error("Metadata field {field} is longer than {max} characters")]
+    MetadataFieldTooLong { field: String, max: u32 },
+
+    #[error("Metadata upload is finalized")]
+    UploadFinalized {},
+
+    #[error("Snapshot interval cannot be zero")]
+    InvalidSnapshotInterval {},
+
     #[error("Cannot get extension")]
     CannotGetExtension {},
 
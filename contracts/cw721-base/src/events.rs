@@ -0,0 +1,189 @@
+use cosmwasm_std::{Addr, Event, Uint128};
+use cw0::Expiration;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::msg::Status;
+
+/// Bumped whenever a key is renamed or removed from one of the events below,
+/// indexers should check `schema_version` before decoding.
+pub const EVENT_SCHEMA_VERSION: &str = "1";
+
+/// Movement of a token, also sent to transfer hooks and kept in its history
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenAction {
+    Mint,
+    Transfer,
+    Send,
+    Burn,
+}
+
+impl TokenAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenAction::Mint => "mint",
+            TokenAction::Transfer => "transfer",
+            TokenAction::Send => "send",
+            TokenAction::Burn => "burn",
+        }
+    }
+}
+
+fn event(ty: &str) -> Event {
+    Event::new(ty).add_attribute("schema_version", EVENT_SCHEMA_VERSION)
+}
+
+fn with_expiration(event: Event, expires: &Expiration) -> Event {
+    match expires {
+        Expiration::AtHeight(height) => {
+            event.add_attribute("expires_at_height", height.to_string())
+        }
+        Expiration::AtTime(time) => {
+            event.add_attribute("expires_at_time", time.seconds().to_string())
+        }
+        Expiration::Never {} => event,
+    }
+}
+
+pub fn mint_event(
+    token_id: &str,
+    minter: &Addr,
+    owner: &Addr,
+    price: Uint128,
+    denom: &str,
+) -> Event {
+    event("mint")
+        .add_attribute("token_id", token_id)
+        .add_attribute("minter", minter)
+        .add_attribute("owner", owner)
+        .add_attribute("price", price)
+        .add_attribute("denom", denom)
+}
+
+/// `Transfer` or `Send` of a token by `sender`, the owner or an approved spender
+pub fn transfer_event(
+    action: TokenAction,
+    token_id: &str,
+    sender: &Addr,
+    from: &Addr,
+    to: &Addr,
+) -> Event {
+    event(action.as_str())
+        .add_attribute("token_id", token_id)
+        .add_attribute("sender", sender)
+        .add_attribute("from", from)
+        .add_attribute("to", to)
+}
+
+pub fn burn_event(token_id: &str, sender: &Addr, owner: &Addr) -> Event {
+    event("burn")
+        .add_attribute("token_id", token_id)
+        .add_attribute("sender", sender)
+        .add_attribute("owner", owner)
+}
+
+pub fn approve_event(token_id: &str, sender: &Addr, spender: &str, expires: &Expiration) -> Event {
+    let event = event("approve")
+        .add_attribute("token_id", token_id)
+        .add_attribute("sender", sender)
+        .add_attribute("spender", spender);
+    with_expiration(event, expires)
+}
+
+pub fn revoke_event(token_id: &str, sender: &Addr, spender: &str) -> Event {
+    event("revoke")
+        .add_attribute("token_id", token_id)
+        .add_attribute("sender", sender)
+        .add_attribute("spender", spender)
+}
+
+pub fn approve_all_event(owner: &Addr, operator: &str, expires: &Expiration) -> Event {
+    let event = event("approve_all")
+        .add_attribute("owner", owner)
+        .add_attribute("operator", operator);
+    with_expiration(event, expires)
+}
+
+pub fn revoke_all_event(owner: &Addr, operator: &str) -> Event {
+    event("revoke_all")
+        .add_attribute("owner", owner)
+        .add_attribute("operator", operator)
+}
+
+/// Metadata uploaded for a token that is not minted yet
+pub fn metadata_event(token_id: &str, sender: &Addr, image: Option<&str>) -> Event {
+    let event = event("metadata")
+        .add_attribute("token_id", token_id)
+        .add_attribute("sender", sender);
+    match image {
+        Some(image) => event.add_attribute("image", image),
+        None => event,
+    }
+}
+
+/// Change of a sale setting, `setting` names the message that changed it
+pub fn sale_config_event(setting: &str, sender: &Addr) -> Event {
+    event("sale_config")
+        .add_attribute("setting", setting)
+        .add_attribute("sender", sender)
+}
+
+/// One event per payout so every amount keeps the same keys
+pub fn withdraw_event(recipient: &str, amount: Uint128, denom: &str) -> Event {
+    event("withdraw")
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", amount)
+        .add_attribute("denom", denom)
+}
+
+/// `action` is one of propose, vote, execute or close
+pub fn proposal_event(action: &str, proposal_id: u64, sender: &Addr, status: Status) -> Event {
+    event("proposal")
+        .add_attribute("action", action)
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("sender", sender)
+        .add_attribute("status", status_str(status))
+}
+
+fn status_str(status: Status) -> &'static str {
+    match status {
+        Status::Pending => "pending",
+        Status::Open => "open",
+        Status::Rejected => "rejected",
+        Status::Passed => "passed",
+        Status::Executed => "executed",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::Timestamp;
+
+    #[test]
+    fn versioned_keys() {
+        let event = approve_event(
+            "7",
+            &Addr::unchecked("owner"),
+            "spender",
+            &Expiration::AtTime(Timestamp::from_seconds(42)),
+        );
+        assert_eq!(event.ty, "approve");
+        let keys: Vec<&str> = event.attributes.iter().map(|a| a.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "schema_version",
+                "token_id",
+                "sender",
+                "spender",
+                "expires_at_time"
+            ]
+        );
+        assert_eq!(event.attributes[4].value, "42");
+
+        let event = proposal_event("vote", 3, &Addr::unchecked("voter"), Status::Passed);
+        assert_eq!(event.attributes[4].value, "passed");
+    }
+}
@@ -1,16 +1,22 @@
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
-use cosmwasm_std::{Addr, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Response, Uint128};
+use cosmwasm_std::{
+    Addr, Api, BankMsg, Binary, BlockInfo, Deps, DepsMut, Env, Event, MessageInfo, Order, Response,
+    StdResult, Storage, SubMsg, Uint128,
+};
 
 use cw2::set_contract_version;
 use cw721::{
     ContractInfoResponse, CustomMsg, Cw721Execute, Cw721ReceiveMsg, Expiration, NumTokensResponse,
 };
+use cw_storage_plus::U64Key;
 
 use crate::constants::*;
 use crate::error::ContractError;
+use crate::events::*;
 use crate::msg::*;
+use crate::payment::*;
 use crate::state::*;
 use crate::state2::*;
 use crate::threshold::Threshold;
@@ -113,30 +119,18 @@ where
         info: MessageInfo,
         msg: MintMsg<T>,
     ) -> Result<Response<C>, ContractError> {
-        let mut fund = Coin {
-            amount: Uint128::from(0u128),
-            denom: String::from("uusd"),
-        };
-
-        for coin in info.clone().funds {
-            if coin.denom == "uusd" {
-                fund = Coin {
-                    amount: fund.amount + coin.amount,
-                    denom: coin.denom,
-                };
-            }
-        }
         let token_minted: NumTokensResponse = deps
             .querier
             .query_wasm_smart(env.contract.address.clone(), &QueryMsg::NumTokens {})?;
 
-        let balance_response: GetBalanceResponse = deps.querier.query_wasm_smart(
-            env.contract.address.clone(),
-            &QueryMsg::GetBalance {
-                owner: info.sender.clone().into_string(),
-            },
-        )?;
-        let balance = balance_response.balance;
+        // the wallet limit counts the mints of the sender, not the tokens it holds
+        let balance = self
+            .wallet_balance
+            .may_load(deps.storage, &info.sender)?
+            .unwrap_or_default();
+
+        let sale_config = self.sale_config.may_load(deps.storage)?.unwrap_or_default();
+        self.check_mint_protection(deps.as_ref(), &env, &sale_config, &info.sender)?;
 
         // let _minter = self.minter.load(deps.storage)?;
 
@@ -176,19 +170,50 @@ where
         //     false
         // };
 
-        let can_mint = token_minted.count < 100 && balance < 1 && fund.amount.u128() == 100000;
+        // once a signer is set, every mint needs a voucher issued for the new owner
+        let voucher = match self.voucher_signer.may_load(deps.storage)? {
+            Some(signer) => {
+                let signed = msg
+                    .voucher
+                    .as_ref()
+                    .ok_or(ContractError::VoucherRequired {})?;
+                self.check_voucher(deps.as_ref(), &env, &signer, signed, &msg.owner)?;
+                Some(&signed.voucher)
+            }
+            None => None,
+        };
+
+        // the voucher price wins, then the dutch auction stage, then the fixed price
+        let auction = self.dutch_auction.may_load(deps.storage)?;
+        let price = match (voucher, &auction) {
+            (Some(voucher), _) => voucher.price,
+            (None, Some(auction)) => {
+                if !auction.is_started(env.block.time) {
+                    return Err(ContractError::DutchAuctionNotStarted {});
+                }
+                auction.price_at(env.block.time)
+            }
+            (None, None) => Uint128::from(MINT_PRICE),
+        };
+        // any overpayment is refunded as change below
+        let payment = must_pay_at_least(&info.funds, MINT_DENOM, price);
+
+        // a voucher replaces the wallet limit with its own quantity
+        let wallet_ok = voucher.is_some() || balance < 1;
+        let can_mint = token_minted.count < MAX_TOKENS && wallet_ok && payment.is_ok();
 
         if !can_mint {
-            if token_minted.count >= 100 {
+            if token_minted.count >= MAX_TOKENS {
                 return Err(ContractError::SoldOut {});
-            } else if balance >= 1 {
+            } else if !wallet_ok {
                 return Err(ContractError::WalletLimitExceeded {});
-            } else if fund.amount.u128() != 100000 {
-                return Err(ContractError::FundMismatch {});
+            } else if let Err(err) = payment {
+                return Err(err);
             } else {
                 return Err(ContractError::Unauthorized {});
             }
         };
+        let change = payment?;
 
         // if info.sender != minter {
         //     return Err(ContractError::Unauthorized {});
@@ -210,6 +235,7 @@ where
             extension: extension_response.extension,
         };
 
+        let owner = token.owner.clone();
         self.tokens
             .update(deps.storage, token_id, |old| match old {
                 Some(pre_token) => match pre_token.owner == "not_yet_set" {
@@ -219,18 +245,104 @@ where
                 None => Err(ContractError::CannotGetExtension {}),
             })?;
 
-        self.wallet_balance.save(
+        self.wallet_balance
+            .save(deps.storage, &info.sender, &(balance + 1))?;
+        self.increment_tokens(deps.storage)?;
+        self.record_ownership(
             deps.storage,
-            &Addr::unchecked(msg.owner.clone()),
-            &(balance + 1),
+            &env,
+            token_id,
+            None,
+            Some(&owner),
+            TokenAction::Mint,
         )?;
-        self.increment_tokens(deps.storage)?;
 
-        Ok(Response::new()
+        // the lock is fixed at mint, a token preset wins over its series
+        if self.token_locks.may_load(deps.storage, token_id)?.is_none() {
+            if let Some(lock) = self.series_lock_for(deps.storage, token_minted.count + 1)? {
+                self.token_locks.save(deps.storage, token_id, &lock)?;
+            }
+        }
+
+        self.record_mint_activity(deps.storage, &env, &info.sender)?;
+        if let Some(voucher) = voucher {
+            let key = U64Key::new(voucher.nonce);
+            let used = self.voucher_nonces.may_load(deps.storage, key.clone())?;
+            self.voucher_nonces
+                .save(deps.storage, key, &(used.unwrap_or_default() + 1))?;
+        }
+
+        let mut res = Response::new()
+            .add_event(mint_event(
+                token_id,
+                &info.sender,
+                &owner,
+                price,
+                MINT_DENOM,
+            ))
+            .add_submessages(self.transfer_hook_msgs(
+                deps.storage,
+                token_id,
+                None,
+                Some(&owner),
+                TokenAction::Mint,
+            )?);
+        // a rebate auction mint only keeps the clearing price once the auction ends
+        let rebate_auction = match (&auction, voucher) {
+            (Some(auction), None) if auction.rebate => {
+                Some(self.dutch_auction_id.load(deps.storage)?)
+            }
+            _ => None,
+        };
+        // the referral commission is owed out of the mint price
+        let mut proceeds = price;
+        if let Some(referrer) = &msg.referrer {
+            if let Some((referrer, commission)) = self.record_referral(
+                deps.storage,
+                deps.api,
+                referrer,
+                &info.sender,
+                &msg.owner,
+                price,
+                rebate_auction,
+            )? {
+                proceeds -= commission;
+                res = res
+                    .add_attribute("referrer", referrer)
+                    .add_attribute("commission", commission);
+            }
+        }
+        if let (Some(_), None) = (&auction, voucher) {
+            self.dutch_auction_last_price.save(deps.storage, &price)?;
+        }
+        if let Some(auction_id) = rebate_auction {
+            self.dutch_auction_payments.update(
+                deps.storage,
+                (U64Key::new(auction_id), &info.sender),
+                |old| -> StdResult<_> {
+                    let mut payment = old.unwrap_or_default();
+                    payment.count += 1;
+                    payment.paid += price;
+                    Ok(payment)
+                },
+            )?;
+        }
+        let revenue = self
+            .mint_revenue
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        self.mint_revenue
+            .save(deps.storage, &(revenue + proceeds))?;
+        if let Some(refund) = refund_msg(&info.sender, MINT_DENOM, change) {
+            res = res.add_message(refund).add_attribute("refund", change);
+        }
+
+        Ok(res
             .add_attribute("action", "mint")
             .add_attribute("minter", info.sender)
             .add_attribute("owner", msg.owner)
-            .add_attribute("token_id", token_id))
+            .add_attribute("token_id", token_id)
+            .add_attribute("price", price))
     }
 }
 
@@ -249,9 +361,18 @@ where
         recipient: String,
         token_id: String,
     ) -> Result<Response<C>, ContractError> {
-        self._transfer_nft(deps, &env, &info, &recipient, &token_id)?;
+        let (event, hooks) = self.transfer_with_hooks(
+            deps,
+            &env,
+            &info,
+            &recipient,
+            &token_id,
+            TokenAction::Transfer,
+        )?;
 
         Ok(Response::new()
+            .add_event(event)
+            .add_submessages(hooks)
             .add_attribute("action", "transfer_nft")
             .add_attribute("sender", info.sender)
             .add_attribute("recipient", recipient)
@@ -268,7 +389,8 @@ where
         msg: Binary,
     ) -> Result<Response<C>, ContractError> {
         // Transfer token
-        self._transfer_nft(deps, &env, &info, &contract, &token_id)?;
+        let (event, hooks) =
+            self.transfer_with_hooks(deps, &env, &info, &contract, &token_id, TokenAction::Send)?;
 
         let send = Cw721ReceiveMsg {
             sender: info.sender.to_string(),
@@ -279,6 +401,8 @@ where
         // Send message
         Ok(Response::new()
             .add_message(send.into_cosmos_msg(contract.clone())?)
+            .add_event(event)
+            .add_submessages(hooks)
             .add_attribute("action", "send_nft")
             .add_attribute("sender", info.sender)
             .add_attribute("recipient", contract)
@@ -297,6 +421,12 @@ where
         self._update_approvals(deps, &env, &info, &spender, &token_id, true, expires)?;
 
         Ok(Response::new()
+            .add_event(approve_event(
+                &token_id,
+                &info.sender,
+                &spender,
+                &expires.unwrap_or_default(),
+            ))
             .add_attribute("action", "approve")
             .add_attribute("sender", info.sender)
             .add_attribute("spender", spender)
@@ -314,6 +444,7 @@ where
         self._update_approvals(deps, &env, &info, &spender, &token_id, false, None)?;
 
         Ok(Response::new()
+            .add_event(revoke_event(&token_id, &info.sender, &spender))
             .add_attribute("action", "revoke")
             .add_attribute("sender", info.sender)
             .add_attribute("spender", spender)
@@ -336,10 +467,11 @@ where
 
         // set the operator for us
         let operator_addr = deps.api.addr_validate(&operator)?;
-        self.operators
-            .save(deps.storage, (&info.sender, &operator_addr), &expires)?;
+        self.check_operator_allowed(deps.as_ref(), &operator_addr)?;
+        self.save_operator(deps.storage, &info.sender, &operator_addr, &expires)?;
 
         Ok(Response::new()
+            .add_event(approve_all_event(&info.sender, &operator, &expires))
             .add_attribute("action", "approve_all")
             .add_attribute("sender", info.sender)
             .add_attribute("operator", operator))
@@ -353,10 +485,10 @@ where
         operator: String,
     ) -> Result<Response<C>, ContractError> {
         let operator_addr = deps.api.addr_validate(&operator)?;
-        self.operators
-            .remove(deps.storage, (&info.sender, &operator_addr));
+        self.remove_operator(deps.storage, &info.sender, &operator_addr);
 
         Ok(Response::new()
+            .add_event(revoke_all_event(&info.sender, &operator))
             .add_attribute("action", "revoke_all")
             .add_attribute("sender", info.sender)
             .add_attribute("operator", operator))
@@ -379,14 +511,109 @@ where
     ) -> Result<TokenInfo<T>, ContractError> {
         let mut token = self.tokens.load(deps.storage, &token_id)?;
         // ensure we have permissions
-        self.check_can_send(deps.as_ref(), env, info, &token)?;
+        self.check_can_send(deps.as_ref(), env, info, token_id, &token)?;
         // set owner and remove existing approvals
         token.owner = deps.api.addr_validate(recipient)?;
         token.approvals = vec![];
         self.tokens.save(deps.storage, &token_id, &token)?;
+        // a listing is only valid for the owner who created it
+        self.listings.remove(deps.storage, token_id)?;
+        self.rental_listings.remove(deps.storage, token_id);
+        // the user role does not survive a transfer
+        self.token_users.remove(deps.storage, token_id)?;
         Ok(token)
     }
 
+    /// `_transfer_nft` returning its event, the settlement of a running rental
+    /// and the notifications for the registered transfer hooks
+    pub fn transfer_with_hooks(
+        &self,
+        mut deps: DepsMut,
+        env: &Env,
+        info: &MessageInfo,
+        recipient: &str,
+        token_id: &str,
+        action: TokenAction,
+    ) -> Result<(Event, Vec<SubMsg<C>>), ContractError> {
+        let from = self.tokens.load(deps.storage, token_id)?.owner;
+        let token = self._transfer_nft(deps.branch(), env, info, recipient, token_id)?;
+        self.record_ownership(
+            deps.storage,
+            &env,
+            token_id,
+            Some(&from),
+            Some(&token.owner),
+            action,
+        )?;
+        let mut msgs: Vec<SubMsg<C>> = self
+            .settle_rental(deps.storage, &env.block, token_id)?
+            .into_iter()
+            .map(SubMsg::new)
+            .collect();
+        msgs.extend(self.transfer_hook_msgs(
+            deps.storage,
+            token_id,
+            Some(&from),
+            Some(&token.owner),
+            action,
+        )?);
+        let event = transfer_event(action, token_id, &info.sender, &from, &token.owner);
+        Ok((event, msgs))
+    }
+
+    /// Ends the escrow of a rental: the owner gets the rent earned so far and
+    /// the renter the unused part
+    pub fn settle_rental(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        token_id: &str,
+    ) -> StdResult<Vec<BankMsg>> {
+        let rental = match self.rentals.may_load(storage, token_id)? {
+            Some(rental) => rental,
+            None => return Ok(vec![]),
+        };
+        self.rentals.remove(storage, token_id);
+        self.release_escrow(storage, &rental.denom, rental.price - rental.paid)?;
+        let earned = rental.earned(block.time);
+        Ok(vec![
+            refund_msg(&rental.owner, &rental.denom, earned - rental.paid),
+            refund_msg(&rental.renter, &rental.denom, rental.price - earned),
+        ]
+        .into_iter()
+        .flatten()
+        .collect())
+    }
+
+    /// One notification per registered hook, `from` is None on mint and `to` on burn.
+    /// Errors of hooks set to ignore them are swallowed in `reply`.
+    pub fn transfer_hook_msgs(
+        &self,
+        storage: &dyn Storage,
+        token_id: &str,
+        from: Option<&Addr>,
+        to: Option<&Addr>,
+        action: TokenAction,
+    ) -> StdResult<Vec<SubMsg<C>>> {
+        self.transfer_hooks
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (k, failure) = item?;
+                let msg = TransferHookMsg {
+                    token_id: token_id.to_string(),
+                    from: from.map(|addr| addr.to_string()),
+                    to: to.map(|addr| addr.to_string()),
+                    action: action.as_str().to_string(),
+                }
+                .into_cosmos_msg(String::from_utf8(k)?)?;
+                Ok(match failure {
+                    HookFailure::Fail => SubMsg::new(msg),
+                    HookFailure::Ignore => SubMsg::reply_on_error(msg, TRANSFER_HOOK_REPLY_ID),
+                })
+            })
+            .collect()
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn _update_approvals(
         &self,
@@ -402,9 +629,18 @@ where
         let mut token = self.tokens.load(deps.storage, &token_id)?;
         // ensure we have permissions
         self.check_can_approve(deps.as_ref(), env, info, &token)?;
+        // approving a locked token would be useless
+        if add && self.token_locks.may_load(deps.storage, token_id)?.is_some() {
+            return Err(ContractError::TransferLocked {
+                token_id: token_id.to_string(),
+            });
+        }
 
         // update the approval list (remove any for the same spender before adding)
         let spender_addr = deps.api.addr_validate(spender)?;
+        if add {
+            self.check_operator_allowed(deps.as_ref(), &spender_addr)?;
+        }
         token.approvals = token
             .approvals
             .into_iter()
@@ -430,6 +666,159 @@ where
         Ok(token)
     }
 
+    /// applies the anti-bot protections enabled in the sale config
+    pub fn check_mint_protection(
+        &self,
+        deps: Deps,
+        env: &Env,
+        config: &SaleConfig,
+        sender: &Addr,
+    ) -> Result<(), ContractError> {
+        if config.reject_contract_senders && is_contract(deps, sender) {
+            return Err(ContractError::ContractSender {});
+        }
+
+        if let Some(max) = config.max_mints_per_block {
+            let block_mints = self.block_mints.may_load(deps.storage)?.unwrap_or_default();
+            if block_mints.count_at(env.block.height) >= max {
+                return Err(ContractError::BlockLimitExceeded {});
+            }
+        }
+
+        let activity = self
+            .mint_activity
+            .may_load(deps.storage, sender)?
+            .unwrap_or_default();
+        if let Some(max) = config.max_mints_per_address_per_block {
+            if activity.block.count_at(env.block.height) >= max {
+                return Err(ContractError::AddressBlockLimitExceeded {});
+            }
+        }
+        if let (Some(cooldown), Some(last_mint)) = (config.mint_cooldown, activity.last_mint) {
+            let ready = last_mint.plus_seconds(cooldown);
+            if env.block.time < ready {
+                return Err(ContractError::MintCooldown {
+                    remaining: ready.seconds() - env.block.time.seconds(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn record_mint_activity(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        sender: &Addr,
+    ) -> StdResult<()> {
+        let mut block_mints = self.block_mints.may_load(storage)?.unwrap_or_default();
+        block_mints.increment(env.block.height);
+        self.block_mints.save(storage, &block_mints)?;
+
+        let mut activity = self
+            .mint_activity
+            .may_load(storage, sender)?
+            .unwrap_or_default();
+        activity.block.increment(env.block.height);
+        activity.last_mint = Some(env.block.time);
+        self.mint_activity.save(storage, sender, &activity)
+    }
+
+    /// credits the referrer's commission for a mint, None while the program is disabled.
+    /// The commission of a rebate auction mint waits for the clearing price.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_referral(
+        &self,
+        storage: &mut dyn Storage,
+        api: &dyn Api,
+        referrer: &str,
+        buyer: &Addr,
+        owner: &str,
+        price: Uint128,
+        rebate_auction: Option<u64>,
+    ) -> Result<Option<(Addr, Uint128)>, ContractError> {
+        let config = self.referral_config.may_load(storage)?.unwrap_or_default();
+        if !config.enabled {
+            return Ok(None);
+        }
+        let referrer = api.addr_validate(referrer)?;
+        if referrer == *buyer || referrer == owner {
+            return Err(ContractError::SelfReferral {});
+        }
+
+        let mut stats = self
+            .referrals
+            .may_load(storage, &referrer)?
+            .unwrap_or_default();
+        stats.referrals += 1;
+        let commission = match rebate_auction {
+            Some(auction_id) => {
+                let key = (&referrer, U64Key::new(auction_id));
+                let count = self.auction_referrals.may_load(storage, key.clone())?;
+                self.auction_referrals
+                    .save(storage, key, &(count.unwrap_or_default() + 1))?;
+                Uint128::zero()
+            }
+            None => {
+                let commission = config.commission(price, stats.earned);
+                stats.volume += price;
+                stats.earned += commission;
+                commission
+            }
+        };
+        self.referrals.save(storage, &referrer, &stats)?;
+        Ok(Some((referrer, commission)))
+    }
+
+    /// checks the voucher was signed by `signer` for `owner` and still has mints left
+    pub fn check_voucher(
+        &self,
+        deps: Deps,
+        env: &Env,
+        signer: &SignerKey,
+        signed: &SignedVoucher,
+        owner: &str,
+    ) -> Result<(), ContractError> {
+        let voucher = &signed.voucher;
+        if voucher.recipient != owner {
+            return Err(ContractError::VoucherRecipientMismatch {});
+        }
+        if voucher.expiry.is_expired(&env.block) {
+            return Err(ContractError::VoucherExpired {});
+        }
+        let used = self
+            .voucher_nonces
+            .may_load(deps.storage, U64Key::new(voucher.nonce))?
+            .unwrap_or_default();
+        if used >= voucher.quantity {
+            return Err(ContractError::VoucherUsed {});
+        }
+
+        let message = voucher.message(&env.contract.address)?;
+        if !signer.verify(deps.api, &message, &signed.signature)? {
+            return Err(ContractError::InvalidSignature {});
+        }
+        Ok(())
+    }
+
+    /// fails if `operator` is a contract the operator registry does not permit
+    pub fn check_operator_allowed(&self, deps: Deps, operator: &Addr) -> Result<(), ContractError> {
+        let filter = self
+            .operator_filter
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        if filter == OperatorFilter::Disabled || !is_contract(deps, operator) {
+            return Ok(());
+        }
+        let status = self.operator_registry.may_load(deps.storage, operator)?;
+        if !filter.permits(status) {
+            return Err(ContractError::OperatorNotAllowed {
+                operator: operator.to_string(),
+            });
+        }
+        Ok(())
+    }
+
     /// returns true iff the sender can execute approve or reject on the contract
     pub fn check_can_approve(
         &self,
@@ -464,12 +853,25 @@ where
         deps: Deps,
         env: &Env,
         info: &MessageInfo,
+        token_id: &str,
         token: &TokenInfo<T>,
     ) -> Result<(), ContractError> {
+        // staked tokens are locked
+        if self.stakes.may_load(deps.storage, token_id)?.is_some() {
+            return Err(ContractError::TokenStaked {
+                token_id: token_id.to_string(),
+            });
+        }
+        if self.token_locks.may_load(deps.storage, token_id)?.is_some() {
+            return Err(ContractError::TransferLocked {
+                token_id: token_id.to_string(),
+            });
+        }
         // owner can send
         if token.owner == info.sender {
             return Ok(());
         }
+        self.check_operator_allowed(deps, &info.sender)?;
 
         // any non-expired token approval can send
         if token
@@ -496,3 +898,10 @@ where
         }
     }
 }
+
+/// raw queries only succeed against existing contracts
+fn is_contract(deps: Deps, addr: &Addr) -> bool {
+    deps.querier
+        .query_wasm_raw(addr.to_string(), b"contract_info".to_vec())
+        .is_ok()
+}
@@ -1,16 +1,25 @@
 use crate::constants::*;
 use crate::error::ContractError;
+use crate::events::*;
 use crate::msg::*;
+use crate::payment::*;
 use crate::state::*;
 use crate::state2::*;
 use crate::traits::*;
+use crate::validation::{is_valid_denom, validate_metadata};
 
 use cosmwasm_std::{
-    Addr, BankMsg, Coin, CosmosMsg, DepsMut, Empty, Env, MessageInfo, Response, Uint128,
+    to_binary, Addr, BankMsg, Binary, Coin, ContractResult, CosmosMsg, Decimal, DepsMut, Empty,
+    Env, Event, MessageInfo, Order, Reply, Response, StdError, StdResult, Storage, SubMsg, Uint128,
+    WasmMsg,
 };
-use cw0::Expiration;
+use cw0::{maybe_addr, Expiration};
+use cw20::Cw20ExecuteMsg;
 use cw3::Vote;
+use cw_storage_plus::{Bound, U64Key};
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryInto;
 
 // const BASE_URI: &str = "ipfs://QmRiLKmhizpnwqpHGeiJnL4G6fsPAxdEdCiDkuJpt7xHPH/";
 
@@ -24,7 +33,170 @@ impl<'a> Cw721ExtendedContract<'a> {
     ) -> Result<Response, ContractError> {
         match msg {
             // ExecuteMsg::FreeMint(msg) => self.execute_free_mint(deps, env, info, msg),
+            ExecuteMsg::Mint(msg) => self.mint_and_index(deps, env, info, msg),
             ExecuteMsg::Withdraw {} => self.execute_withdraw(deps, env, info),
+            ExecuteMsg::SetDutchAuction { auction } => {
+                self.execute_set_dutch_auction(deps, env, info, auction)
+            }
+            ExecuteMsg::ClaimRebate { auction_id } => {
+                self.execute_claim_rebate(deps, env, info, auction_id)
+            }
+            ExecuteMsg::SetVoucherSigner { signer } => {
+                self.execute_set_voucher_signer(deps, env, info, signer)
+            }
+            ExecuteMsg::SetReferralConfig { config } => {
+                self.execute_set_referral_config(deps, env, info, config)
+            }
+            ExecuteMsg::ClaimReferralCommission {} => {
+                self.execute_claim_referral_commission(deps, env, info)
+            }
+            ExecuteMsg::SetSaleConfig { config } => {
+                self.execute_set_sale_config(deps, env, info, config)
+            }
+            ExecuteMsg::ListNft {
+                token_id,
+                price,
+                denom,
+                expires,
+            } => self.execute_list_nft(deps, env, info, token_id, price, denom, expires),
+            ExecuteMsg::CancelListing { token_id } => {
+                self.execute_cancel_listing(deps, env, info, token_id)
+            }
+            ExecuteMsg::BuyNft { token_id } => self.execute_buy_nft(deps, env, info, token_id),
+            ExecuteMsg::CreateAuction {
+                token_id,
+                denom,
+                reserve_price,
+                min_increment,
+                duration,
+                extension,
+            } => self.execute_create_auction(
+                deps,
+                env,
+                info,
+                token_id,
+                denom,
+                reserve_price,
+                min_increment,
+                duration,
+                extension,
+            ),
+            ExecuteMsg::PlaceBid { auction_id } => {
+                self.execute_place_bid(deps, env, info, auction_id)
+            }
+            ExecuteMsg::SettleAuction { auction_id } => {
+                self.execute_settle_auction(deps, env, info, auction_id)
+            }
+            ExecuteMsg::CancelAuction { auction_id } => {
+                self.execute_cancel_auction(deps, env, info, auction_id)
+            }
+            ExecuteMsg::MakeOffer {
+                target,
+                denom,
+                expires,
+            } => self.execute_make_offer(deps, env, info, target, denom, expires),
+            ExecuteMsg::AcceptOffer { offer_id, token_id } => {
+                self.execute_accept_offer(deps, env, info, offer_id, token_id)
+            }
+            ExecuteMsg::CancelOffer { offer_id } => {
+                self.execute_cancel_offer(deps, env, info, offer_id)
+            }
+            ExecuteMsg::ProposeSwap {
+                counterparty,
+                offered_tokens,
+                requested_tokens,
+                requested_funds,
+                expires,
+            } => self.execute_propose_swap(
+                deps,
+                env,
+                info,
+                counterparty,
+                offered_tokens,
+                requested_tokens,
+                requested_funds,
+                expires,
+            ),
+            ExecuteMsg::SetTransferLock { token_ids, lock } => {
+                self.execute_set_transfer_lock(deps, env, info, token_ids, lock)
+            }
+            ExecuteMsg::SetSeriesLock {
+                series,
+                series_lock,
+            } => self.execute_set_series_lock(deps, env, info, series, series_lock),
+            ExecuteMsg::Burn { token_id } => self.execute_burn(deps, env, info, token_id),
+            ExecuteMsg::SetOperatorFilter { filter } => {
+                self.execute_set_operator_filter(deps, env, info, filter)
+            }
+            ExecuteMsg::SetOperatorStatus { operators, status } => {
+                self.execute_set_operator_status(deps, env, info, operators, status)
+            }
+            ExecuteMsg::ApproveWithPermit { permit, signature } => {
+                self.execute_approve_with_permit(deps, env, info, permit, signature)
+            }
+            ExecuteMsg::RevokeAllOperators {} => self.execute_revoke_all_operators(deps, env, info),
+            ExecuteMsg::PruneOperatorGrants {
+                operator,
+                start_after,
+                limit,
+            } => self.execute_prune_operator_grants(deps, env, info, operator, start_after, limit),
+            ExecuteMsg::TakeSnapshot {} => self.execute_take_snapshot(deps, env, info),
+            ExecuteMsg::SetSnapshotInterval { interval } => {
+                self.execute_set_snapshot_interval(deps, env, info, interval)
+            }
+            ExecuteMsg::SetMetadataRules { rules } => {
+                self.execute_set_metadata_rules(deps, env, info, rules)
+            }
+            ExecuteMsg::FinalizeUpload { rarity_method } => {
+                self.execute_finalize_upload(deps, env, info, rarity_method)
+            }
+            ExecuteMsg::SetHistoryRetention { limit } => {
+                self.execute_set_history_retention(deps, env, info, limit)
+            }
+            ExecuteMsg::SetTransferHook { contract, failure } => {
+                self.execute_set_transfer_hook(deps, env, info, contract, failure)
+            }
+            ExecuteMsg::BatchTransferNft {
+                recipient,
+                token_ids,
+            } => self.execute_batch_transfer_nft(deps, env, info, recipient, token_ids),
+            ExecuteMsg::BatchSendNft {
+                contract,
+                token_ids,
+                msg,
+            } => self.execute_batch_send_nft(deps, env, info, contract, token_ids, msg),
+            ExecuteMsg::SetUser {
+                token_id,
+                user,
+                expires,
+            } => self.execute_set_user(deps, env, info, token_id, user, expires),
+            ExecuteMsg::ListForRent {
+                token_id,
+                price,
+                denom,
+                duration,
+            } => self.execute_list_for_rent(deps, env, info, token_id, price, denom, duration),
+            ExecuteMsg::CancelRentalListing { token_id } => {
+                self.execute_cancel_rental_listing(deps, env, info, token_id)
+            }
+            ExecuteMsg::Rent { token_id } => self.execute_rent(deps, env, info, token_id),
+            ExecuteMsg::ClaimRent { token_id } => {
+                self.execute_claim_rent(deps, env, info, token_id)
+            }
+            ExecuteMsg::SetStakingConfig { config } => {
+                self.execute_set_staking_config(deps, env, info, config)
+            }
+            ExecuteMsg::Stake { token_ids } => self.execute_stake(deps, env, info, token_ids),
+            ExecuteMsg::Unstake { token_ids } => self.execute_unstake(deps, env, info, token_ids),
+            ExecuteMsg::ClaimStakingRewards { token_ids } => {
+                self.execute_claim_staking_rewards(deps, env, info, token_ids)
+            }
+            ExecuteMsg::AcceptSwap { swap_id } => {
+                self.execute_accept_swap(deps, env, info, swap_id)
+            }
+            ExecuteMsg::CancelSwap { swap_id } => {
+                self.execute_cancel_swap(deps, env, info, swap_id)
+            }
             // ExecuteMsg::SetArtReveal { art_reveal } => {
             //     self.execute_set_art_reveal(deps, env, info, art_reveal)
             // }
@@ -38,89 +210,1831 @@ impl<'a> Cw721ExtendedContract<'a> {
             ExecuteMsg::AddExtension(msg) => {
                 self.execute_add_extension(deps, env, info, msg.token_id, msg.extension)
             }
-            // CW3
-            ExecuteMsg::Propose {
-                title,
-                description,
-                msgs,
-                latest,
-            } => self.execute_propose(deps, env, info, title, description, msgs, latest),
-            ExecuteMsg::Vote { proposal_id, vote } => {
-                self.execute_vote(deps, env, info, proposal_id, vote)
+            // CW3
+            ExecuteMsg::Propose {
+                title,
+                description,
+                msgs,
+                latest,
+            } => self.execute_propose(deps, env, info, title, description, msgs, latest),
+            ExecuteMsg::Vote { proposal_id, vote } => {
+                self.execute_vote(deps, env, info, proposal_id, vote)
+            }
+            ExecuteMsg::Execute { proposal_id } => {
+                self.execute_execute(deps, env, info, proposal_id)
+            }
+            ExecuteMsg::Close { proposal_id } => self.execute_close(deps, env, info, proposal_id),
+
+            _ => Cw721ExtendedContract::default()._execute(deps, env, info, msg),
+        }
+    }
+
+    pub fn reply(&self, _deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+        match msg.id {
+            // only hooks set to ignore errors reply, and only on error
+            TRANSFER_HOOK_REPLY_ID => {
+                let error = match msg.result {
+                    ContractResult::Ok(_) => String::new(),
+                    ContractResult::Err(err) => err,
+                };
+                Ok(Response::new()
+                    .add_attribute("action", "transfer_hook_failed")
+                    .add_attribute("error", error))
+            }
+            id => Err(ContractError::UnknownReplyId { id }),
+        }
+    }
+}
+
+impl<'a> Cw721ExtendedExecute<Extension> for Cw721ExtendedContract<'a> {
+    fn execute_withdraw(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        //     let team_signed = self
+        //         .cw3_signature
+        //         .may_load(deps.storage, &Addr::unchecked(ADDR_TEAM))?
+        //         .unwrap_or(false);
+        //     let pro_signed = self
+        //         .cw3_signature
+        //         .may_load(deps.storage, &Addr::unchecked(ADDR_PRO))?
+        //         .unwrap_or(false);
+        //     let treas_signed = self
+        //         .cw3_signature
+        //         .may_load(deps.storage, &Addr::unchecked(ADDR_TREAS))?
+        //         .unwrap_or(false);
+
+        //     if !team_signed || !pro_signed || !treas_signed {
+        //         return Err(ContractError::NotAllSigned {});
+        //     }
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::NotMinter {});
+        }
+
+        // funds escrowed for users are not the team's to withdraw
+        let escrowed = self
+            .escrow
+            .may_load(deps.storage, "uluna")?
+            .unwrap_or_default();
+        let current_uluna_amount = deps
+            .querier
+            .query_balance(env.contract.address.to_string(), "uluna")?
+            .amount
+            .saturating_sub(escrowed);
+
+        let team_portion = current_uluna_amount * Uint128::from(30u128) / Uint128::from(100u128);
+        let pro_portion = current_uluna_amount * Uint128::from(14u128) / Uint128::from(100u128);
+        let treas_portion = current_uluna_amount * Uint128::from(56u128) / Uint128::from(100u128);
+
+        // self.cw3_signature
+        //     .save(deps.storage, &Addr::unchecked(ADDR_TEAM), &(false))?;
+        // self.cw3_signature
+        //     .save(deps.storage, &Addr::unchecked(ADDR_PRO), &(false))?;
+        // self.cw3_signature
+        //     .save(deps.storage, &Addr::unchecked(ADDR_TREAS), &(false))?;
+
+        let events = vec![
+            withdraw_event(ADDR_TEAM, team_portion, "uluna"),
+            withdraw_event(ADDR_PRO, pro_portion, "uluna"),
+            withdraw_event(ADDR_TREAS, treas_portion, "uluna"),
+        ];
+
+        let mut messages: Vec<CosmosMsg> = vec![];
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: ADDR_TEAM.to_string(),
+            amount: vec![Coin {
+                denom: String::from("uluna"),
+                amount: team_portion,
+            }],
+        }));
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: ADDR_PRO.to_string(),
+            amount: vec![Coin {
+                denom: String::from("uluna"),
+                amount: pro_portion,
+            }],
+        }));
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: ADDR_TREAS.to_string(),
+            amount: vec![Coin {
+                denom: String::from("uluna"),
+                amount: treas_portion,
+            }],
+        }));
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_events(events)
+            .add_attribute("action", "withdraw")
+            .add_attribute("sender", info.sender)
+            .add_attribute("amount", current_uluna_amount)
+            .add_attribute("denom", "uluna"))
+    }
+
+    fn execute_set_dutch_auction(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction: Option<DutchAuction>,
+    ) -> Result<Response, ContractError> {
+        if info.sender != self.minter.load(deps.storage)? {
+            return Err(ContractError::NotMinter {});
+        }
+        if let Some(auction) = &auction {
+            if !auction.is_valid() {
+                return Err(ContractError::InvalidDutchAuction {});
+            }
+        }
+        // a replaced or removed auction clears at the lowest price paid so far,
+        // its rebates stay claimable under its id
+        if let Some(current) = self.dutch_auction.may_load(deps.storage)? {
+            let final_price = self
+                .dutch_auction_clearing_price(deps.storage, &current, env.block.time)?
+                .or(self.dutch_auction_last_price.may_load(deps.storage)?);
+            if let (true, Some(price)) = (current.rebate, final_price) {
+                let auction_id = self.dutch_auction_id.load(deps.storage)?;
+                self.dutch_auction_final_price.save(
+                    deps.storage,
+                    U64Key::new(auction_id),
+                    &price,
+                )?;
+            }
+        }
+
+        match auction {
+            Some(auction) => {
+                let auction_id = self
+                    .dutch_auction_id
+                    .may_load(deps.storage)?
+                    .unwrap_or_default()
+                    + 1;
+                self.dutch_auction_id.save(deps.storage, &auction_id)?;
+                self.dutch_auction_last_price.remove(deps.storage);
+                self.dutch_auction.save(deps.storage, &auction)?;
+                Ok(Response::new()
+                    .add_event(sale_config_event("set_dutch_auction", &info.sender))
+                    .add_attribute("action", "set_dutch_auction")
+                    .add_attribute("auction_id", auction_id.to_string())
+                    .add_attribute("start_price", auction.start_price)
+                    .add_attribute("floor_price", auction.floor_price))
+            }
+            None => {
+                self.dutch_auction.remove(deps.storage);
+                Ok(Response::new()
+                    .add_event(sale_config_event("remove_dutch_auction", &info.sender))
+                    .add_attribute("action", "remove_dutch_auction"))
+            }
+        }
+    }
+
+    fn execute_claim_rebate(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        let latest_id = self
+            .dutch_auction_id
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        let auction_id = auction_id.unwrap_or(latest_id);
+        let running = match self.dutch_auction.may_load(deps.storage)? {
+            Some(auction) if auction_id == latest_id => Some(auction),
+            _ => None,
+        };
+        // a replaced or removed auction keeps its clearing price for its rebates
+        let clearing_price = match running {
+            Some(auction) => {
+                if !auction.rebate {
+                    return Err(ContractError::RebateDisabled {});
+                }
+                self.dutch_auction_clearing_price(deps.storage, &auction, env.block.time)?
+                    .ok_or(ContractError::DutchAuctionNotEnded {})?
+            }
+            None => self
+                .dutch_auction_final_price
+                .may_load(deps.storage, U64Key::new(auction_id))?
+                .ok_or(ContractError::NothingToClaim {})?,
+        };
+
+        let key = (U64Key::new(auction_id), &info.sender);
+        let mut payment = self
+            .dutch_auction_payments
+            .may_load(deps.storage, key.clone())?
+            .unwrap_or_default();
+        let owed = clearing_price * Uint128::from(payment.count);
+        let rebate = payment.paid.saturating_sub(owed);
+        if rebate.is_zero() {
+            return Err(ContractError::NothingToClaim {});
+        }
+        payment.paid = owed;
+        self.dutch_auction_payments
+            .save(deps.storage, key, &payment)?;
+        let revenue = self
+            .mint_revenue
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        self.mint_revenue
+            .save(deps.storage, &revenue.saturating_sub(rebate))?;
+
+        Ok(Response::new()
+            .add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: vec![Coin {
+                    denom: String::from(MINT_DENOM),
+                    amount: rebate,
+                }],
+            })
+            .add_attribute("action", "claim_rebate")
+            .add_attribute("sender", info.sender)
+            .add_attribute("clearing_price", clearing_price)
+            .add_attribute("rebate", rebate))
+    }
+
+    fn execute_set_voucher_signer(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        signer: Option<SignerKey>,
+    ) -> Result<Response, ContractError> {
+        if info.sender != self.minter.load(deps.storage)? {
+            return Err(ContractError::NotMinter {});
+        }
+        match signer {
+            Some(signer) => {
+                self.voucher_signer.save(deps.storage, &signer)?;
+                Ok(Response::new()
+                    .add_event(sale_config_event("set_voucher_signer", &info.sender))
+                    .add_attribute("action", "set_voucher_signer"))
+            }
+            None => {
+                self.voucher_signer.remove(deps.storage);
+                Ok(Response::new()
+                    .add_event(sale_config_event("remove_voucher_signer", &info.sender))
+                    .add_attribute("action", "remove_voucher_signer"))
+            }
+        }
+    }
+
+    fn execute_set_referral_config(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        config: ReferralConfig,
+    ) -> Result<Response, ContractError> {
+        if info.sender != self.minter.load(deps.storage)? {
+            return Err(ContractError::NotMinter {});
+        }
+        if !config.is_valid() {
+            return Err(ContractError::InvalidReferralConfig {});
+        }
+        self.referral_config.save(deps.storage, &config)?;
+        Ok(Response::new()
+            .add_event(sale_config_event("set_referral_config", &info.sender))
+            .add_attribute("action", "set_referral_config")
+            .add_attribute("enabled", config.enabled.to_string())
+            .add_attribute("commission_bps", config.commission_bps.to_string()))
+    }
+
+    fn execute_claim_referral_commission(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        let earned = self
+            .referrals
+            .may_load(deps.storage, &info.sender)?
+            .unwrap_or_default()
+            .earned;
+        let (mut stats, settled) =
+            self.settled_referral_stats(deps.storage, &info.sender, env.block.time)?;
+        let claimable = stats.claimable();
+        if claimable.is_zero() {
+            return Err(ContractError::NothingToClaim {});
+        }
+        for auction_id in settled {
+            self.auction_referrals
+                .remove(deps.storage, (&info.sender, U64Key::new(auction_id)));
+        }
+        // commissions settled now come out of the clearing price kept at mint
+        let revenue = self
+            .mint_revenue
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        self.mint_revenue
+            .save(deps.storage, &revenue.saturating_sub(stats.earned - earned))?;
+        stats.claimed = stats.earned;
+        self.referrals.save(deps.storage, &info.sender, &stats)?;
+
+        Ok(Response::new()
+            .add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: vec![Coin {
+                    denom: String::from(MINT_DENOM),
+                    amount: claimable,
+                }],
+            })
+            .add_attribute("action", "claim_referral_commission")
+            .add_attribute("referrer", info.sender)
+            .add_attribute("amount", claimable))
+    }
+
+    fn execute_set_sale_config(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        config: SaleConfig,
+    ) -> Result<Response, ContractError> {
+        if info.sender != self.minter.load(deps.storage)? {
+            return Err(ContractError::NotMinter {});
+        }
+        self.sale_config.save(deps.storage, &config)?;
+        Ok(Response::new()
+            .add_event(sale_config_event("set_sale_config", &info.sender))
+            .add_attribute("action", "set_sale_config"))
+    }
+
+    fn execute_list_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        price: Uint128,
+        denom: String,
+        expires: Option<Expiration>,
+    ) -> Result<Response, ContractError> {
+        // a zero price gives the token away, a malformed denom can never be paid
+        if price.is_zero() || !is_valid_denom(&denom) {
+            return Err(ContractError::InvalidListing {});
+        }
+        let token = self.tokens.load(deps.storage, &token_id)?;
+        // owner or an approved operator can list
+        self.check_can_send(deps.as_ref(), &env, &info, &token_id, &token)?;
+
+        // reject expired data as invalid
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+
+        let listing = Listing {
+            token_id: token_id.clone(),
+            seller: token.owner,
+            price,
+            denom,
+            expires,
+        };
+        self.listings.save(deps.storage, &token_id, &listing)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "list_nft")
+            .add_attribute("sender", info.sender)
+            .add_attribute("seller", listing.seller)
+            .add_attribute("token_id", token_id)
+            .add_attribute("price", listing.price)
+            .add_attribute("denom", listing.denom))
+    }
+
+    fn execute_cancel_listing(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+    ) -> Result<Response, ContractError> {
+        let token = self.tokens.load(deps.storage, &token_id)?;
+        self.check_can_send(deps.as_ref(), &env, &info, &token_id, &token)?;
+        self.listings.load(deps.storage, &token_id)?;
+        self.listings.remove(deps.storage, &token_id)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "cancel_listing")
+            .add_attribute("sender", info.sender)
+            .add_attribute("token_id", token_id))
+    }
+
+    fn execute_buy_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+    ) -> Result<Response, ContractError> {
+        let listing = self.listings.load(deps.storage, &token_id)?;
+        if listing.expires.is_expired(&env.block) {
+            return Err(ContractError::ListingExpired {});
+        }
+        if listing.seller == info.sender {
+            return Err(ContractError::CannotBuyOwnListing {});
+        }
+        let change = must_pay_at_least(&info.funds, &listing.denom, listing.price)?;
+
+        // move the token on behalf of the seller, this also clears the listing
+        let seller_info = MessageInfo {
+            sender: listing.seller.clone(),
+            funds: vec![],
+        };
+        let (event, hooks) = self.transfer_with_hooks(
+            deps,
+            &env,
+            &seller_info,
+            info.sender.as_str(),
+            &token_id,
+            TokenAction::Transfer,
+        )?;
+
+        let (royalty_address, royalty) = royalty_payment(listing.price);
+        let proceeds = listing.price - royalty;
+        let messages: Vec<BankMsg> = vec![
+            refund_msg(&listing.seller, &listing.denom, proceeds),
+            refund_msg(&royalty_address, &listing.denom, royalty),
+            refund_msg(&info.sender, &listing.denom, change),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_event(event)
+            .add_submessages(hooks)
+            .add_attribute("action", "buy_nft")
+            .add_attribute("buyer", info.sender)
+            .add_attribute("seller", listing.seller)
+            .add_attribute("token_id", token_id)
+            .add_attribute("price", listing.price)
+            .add_attribute("denom", listing.denom)
+            .add_attribute("royalty", royalty))
+    }
+
+    fn execute_create_auction(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        denom: String,
+        reserve_price: Uint128,
+        min_increment: Uint128,
+        duration: u64,
+        extension: u64,
+    ) -> Result<Response, ContractError> {
+        // a zero increment lets equal bids extend the auction forever,
+        // a zero reserve gives the token away for nothing
+        if reserve_price.is_zero() || min_increment.is_zero() || duration == 0 {
+            return Err(ContractError::InvalidAuction {});
+        }
+        // escrow the token, this checks the sender can send it
+        let seller = self.tokens.load(deps.storage, &token_id)?.owner;
+        let (event, hooks) = self.transfer_with_hooks(
+            deps.branch(),
+            &env,
+            &info,
+            env.contract.address.as_str(),
+            &token_id,
+            TokenAction::Transfer,
+        )?;
+
+        let auction = Auction {
+            token_id: token_id.clone(),
+            seller,
+            denom,
+            reserve_price,
+            min_increment,
+            end_time: env.block.time.plus_seconds(duration),
+            extension,
+            highest_bid: None,
+            state: AuctionState::Open,
+        };
+        let id = self.next_auction_id(deps.storage)?;
+        self.auctions
+            .save(deps.storage, U64Key::new(id), &auction)?;
+
+        Ok(Response::new()
+            .add_event(event)
+            .add_submessages(hooks)
+            .add_attribute("action", "create_auction")
+            .add_attribute("sender", info.sender)
+            .add_attribute("seller", auction.seller)
+            .add_attribute("auction_id", id.to_string())
+            .add_attribute("token_id", token_id)
+            .add_attribute("end_time", auction.end_time.seconds().to_string()))
+    }
+
+    fn execute_place_bid(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+    ) -> Result<Response, ContractError> {
+        let mut auction = self.auctions.load(deps.storage, U64Key::new(auction_id))?;
+        if auction.state != AuctionState::Open {
+            return Err(ContractError::AuctionNotOpen {});
+        }
+        if auction.is_ended(env.block.time) {
+            return Err(ContractError::AuctionEnded {});
+        }
+        if auction.seller == info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        // the whole payment is the bid
+        let min_bid = auction.min_bid();
+        let amount = min_bid
+            + must_pay_at_least(&info.funds, &auction.denom, min_bid).map_err(|err| match err {
+                ContractError::InsufficientFunds { .. } => ContractError::BidTooLow { min_bid },
+                err => err,
+            })?;
+
+        // refund the bidder we outbid right away
+        let outbid = auction.highest_bid.replace(Bid {
+            bidder: info.sender.clone(),
+            amount,
+        });
+        self.add_escrow(deps.storage, &auction.denom, amount)?;
+        if let Some(bid) = &outbid {
+            self.release_escrow(deps.storage, &auction.denom, bid.amount)?;
+        }
+        let refund = outbid.and_then(|bid| refund_msg(&bid.bidder, &auction.denom, bid.amount));
+
+        // late bids extend the auction
+        let extended_end = env.block.time.plus_seconds(auction.extension);
+        if extended_end > auction.end_time {
+            auction.end_time = extended_end;
+        }
+        self.auctions
+            .save(deps.storage, U64Key::new(auction_id), &auction)?;
+
+        Ok(Response::new()
+            .add_messages(refund)
+            .add_attribute("action", "place_bid")
+            .add_attribute("bidder", info.sender)
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("amount", amount)
+            .add_attribute("end_time", auction.end_time.seconds().to_string()))
+    }
+
+    fn execute_settle_auction(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+    ) -> Result<Response, ContractError> {
+        let mut auction = self.auctions.load(deps.storage, U64Key::new(auction_id))?;
+        if auction.state != AuctionState::Open {
+            return Err(ContractError::AuctionNotOpen {});
+        }
+        if !auction.is_ended(env.block.time) {
+            return Err(ContractError::AuctionNotEnded {});
+        }
+        auction.state = AuctionState::Settled;
+        self.auctions
+            .save(deps.storage, U64Key::new(auction_id), &auction)?;
+        if let Some(bid) = &auction.highest_bid {
+            self.release_escrow(deps.storage, &auction.denom, bid.amount)?;
+        }
+
+        // the token goes to the winner, or back to the seller without bids
+        let recipient = match &auction.highest_bid {
+            Some(bid) => bid.bidder.clone(),
+            None => auction.seller.clone(),
+        };
+        let escrow_info = MessageInfo {
+            sender: env.contract.address.clone(),
+            funds: vec![],
+        };
+        let (event, hooks) = self.transfer_with_hooks(
+            deps,
+            &env,
+            &escrow_info,
+            recipient.as_str(),
+            &auction.token_id,
+            TokenAction::Transfer,
+        )?;
+
+        let mut messages: Vec<BankMsg> = vec![];
+        if let Some(bid) = &auction.highest_bid {
+            let (royalty_address, royalty) = royalty_payment(bid.amount);
+            messages.extend(refund_msg(
+                &auction.seller,
+                &auction.denom,
+                bid.amount - royalty,
+            ));
+            messages.extend(refund_msg(&royalty_address, &auction.denom, royalty));
+        }
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_event(event)
+            .add_submessages(hooks)
+            .add_attribute("action", "settle_auction")
+            .add_attribute("sender", info.sender)
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("token_id", auction.token_id)
+            .add_attribute("recipient", recipient))
+    }
+
+    fn execute_cancel_auction(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+    ) -> Result<Response, ContractError> {
+        let mut auction = self.auctions.load(deps.storage, U64Key::new(auction_id))?;
+        if auction.seller != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+        if auction.state != AuctionState::Open {
+            return Err(ContractError::AuctionNotOpen {});
+        }
+        if auction.highest_bid.is_some() {
+            return Err(ContractError::AuctionHasBids {});
+        }
+        auction.state = AuctionState::Cancelled;
+        self.auctions
+            .save(deps.storage, U64Key::new(auction_id), &auction)?;
+
+        let escrow_info = MessageInfo {
+            sender: env.contract.address.clone(),
+            funds: vec![],
+        };
+        let (event, hooks) = self.transfer_with_hooks(
+            deps,
+            &env,
+            &escrow_info,
+            auction.seller.as_str(),
+            &auction.token_id,
+            TokenAction::Transfer,
+        )?;
+
+        Ok(Response::new()
+            .add_event(event)
+            .add_submessages(hooks)
+            .add_attribute("action", "cancel_auction")
+            .add_attribute("sender", info.sender)
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("token_id", auction.token_id))
+    }
+
+    fn execute_make_offer(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        target: OfferTarget,
+        denom: String,
+        expires: Option<Expiration>,
+    ) -> Result<Response, ContractError> {
+        // reject expired data as invalid
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+        if let OfferTarget::Token { token_id } = &target {
+            self.tokens.load(deps.storage, token_id)?;
+        }
+        // the whole payment is escrowed
+        let amount = must_pay_at_least(&info.funds, &denom, Uint128::zero())?;
+        if amount.is_zero() {
+            return Err(ContractError::ZeroOffer {});
+        }
+
+        let offer = Offer {
+            bidder: info.sender.clone(),
+            target,
+            amount,
+            denom,
+            expires,
+        };
+        let id = self.next_offer_id(deps.storage)?;
+        self.offers.save(deps.storage, U64Key::new(id), &offer)?;
+        self.add_escrow(deps.storage, &offer.denom, amount)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "make_offer")
+            .add_attribute("bidder", info.sender)
+            .add_attribute("offer_id", id.to_string())
+            .add_attribute("amount", offer.amount)
+            .add_attribute("denom", offer.denom))
+    }
+
+    fn execute_accept_offer(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        offer_id: u64,
+        token_id: String,
+    ) -> Result<Response, ContractError> {
+        let offer = self.offers.load(deps.storage, U64Key::new(offer_id))?;
+        if offer.expires.is_expired(&env.block) {
+            return Err(ContractError::OfferExpired {});
+        }
+
+        let token = self.tokens.load(deps.storage, &token_id)?;
+        let matches = match &offer.target {
+            OfferTarget::Token { token_id: target } => *target == token_id,
+            OfferTarget::Collection {} => true,
+            OfferTarget::Trait { trait_type, value } => token
+                .extension
+                .as_ref()
+                .and_then(|meta| meta.attributes.as_ref())
+                .map(|traits| {
+                    traits
+                        .iter()
+                        .any(|t| t.trait_type == *trait_type && t.value == *value)
+                })
+                .unwrap_or(false),
+        };
+        if !matches {
+            return Err(ContractError::OfferTargetMismatch {});
+        }
+        if token.owner == offer.bidder {
+            return Err(ContractError::CannotAcceptOwnOffer {});
+        }
+
+        // settle: the offer is consumed and the token moves to the bidder
+        self.offers.remove(deps.storage, U64Key::new(offer_id))?;
+        self.release_escrow(deps.storage, &offer.denom, offer.amount)?;
+        let seller = token.owner;
+        let (event, hooks) = self.transfer_with_hooks(
+            deps,
+            &env,
+            &info,
+            offer.bidder.as_str(),
+            &token_id,
+            TokenAction::Transfer,
+        )?;
+
+        let (royalty_address, royalty) = royalty_payment(offer.amount);
+        let messages: Vec<BankMsg> = vec![
+            refund_msg(&seller, &offer.denom, offer.amount - royalty),
+            refund_msg(&royalty_address, &offer.denom, royalty),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_event(event)
+            .add_submessages(hooks)
+            .add_attribute("action", "accept_offer")
+            .add_attribute("sender", info.sender)
+            .add_attribute("seller", seller)
+            .add_attribute("bidder", offer.bidder)
+            .add_attribute("offer_id", offer_id.to_string())
+            .add_attribute("token_id", token_id)
+            .add_attribute("amount", offer.amount)
+            .add_attribute("royalty", royalty))
+    }
+
+    fn execute_cancel_offer(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        offer_id: u64,
+    ) -> Result<Response, ContractError> {
+        let offer = self.offers.load(deps.storage, U64Key::new(offer_id))?;
+        if offer.bidder != info.sender && !offer.expires.is_expired(&env.block) {
+            return Err(ContractError::Unauthorized {});
+        }
+        self.offers.remove(deps.storage, U64Key::new(offer_id))?;
+        self.release_escrow(deps.storage, &offer.denom, offer.amount)?;
+
+        Ok(Response::new()
+            .add_messages(refund_msg(&offer.bidder, &offer.denom, offer.amount))
+            .add_attribute("action", "cancel_offer")
+            .add_attribute("sender", info.sender)
+            .add_attribute("bidder", offer.bidder)
+            .add_attribute("offer_id", offer_id.to_string()))
+    }
+
+    fn execute_propose_swap(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        counterparty: String,
+        offered_tokens: Vec<String>,
+        requested_tokens: Vec<String>,
+        requested_funds: Vec<Coin>,
+        expires: Option<Expiration>,
+    ) -> Result<Response, ContractError> {
+        let counterparty = deps.api.addr_validate(&counterparty)?;
+        if counterparty == info.sender {
+            return Err(ContractError::CannotSwapWithSelf {});
+        }
+        // reject expired data as invalid
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+
+        // one coin per denom, so accepting pays exactly what is sent out
+        let offered_funds = merge_coins(info.funds.clone());
+        let requested_funds = merge_coins(requested_funds);
+        if (offered_tokens.is_empty() && requested_tokens.is_empty())
+            || (offered_tokens.is_empty() && offered_funds.is_empty())
+            || (requested_tokens.is_empty() && requested_funds.is_empty())
+        {
+            return Err(ContractError::EmptySwap {});
+        }
+
+        let mut seen: Vec<&String> = vec![];
+        for token_id in offered_tokens.iter().chain(requested_tokens.iter()) {
+            if seen.contains(&token_id) {
+                return Err(ContractError::DuplicateToken {
+                    token_id: token_id.clone(),
+                });
+            }
+            seen.push(token_id);
+        }
+
+        // the proposer must be able to send what is offered
+        for token_id in offered_tokens.iter() {
+            let token = self.tokens.load(deps.storage, token_id)?;
+            self.check_can_send(deps.as_ref(), &env, &info, token_id, &token)?;
+        }
+        for token_id in requested_tokens.iter() {
+            let token = self.tokens.load(deps.storage, token_id)?;
+            if token.owner != counterparty {
+                return Err(ContractError::TokenNotOwned {
+                    token_id: token_id.clone(),
+                    owner: counterparty.to_string(),
+                });
+            }
+        }
+
+        let swap = Swap {
+            proposer: info.sender.clone(),
+            offered_tokens,
+            offered_funds,
+            counterparty,
+            requested_tokens,
+            requested_funds,
+            expires,
+        };
+        let id = self.next_swap_id(deps.storage)?;
+        self.save_swap(deps.storage, id, &swap)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "propose_swap")
+            .add_attribute("proposer", info.sender)
+            .add_attribute("counterparty", swap.counterparty)
+            .add_attribute("swap_id", id.to_string()))
+    }
+
+    fn execute_accept_swap(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        swap_id: u64,
+    ) -> Result<Response, ContractError> {
+        let swap = self.swaps.load(deps.storage, U64Key::new(swap_id))?;
+        if swap.counterparty != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+        if swap.expires.is_expired(&env.block) {
+            return Err(ContractError::SwapExpired {});
+        }
+        let change = must_pay_coins(&info.funds, &swap.requested_funds)?;
+        self.remove_swap(deps.storage, swap_id, &swap)?;
+
+        // the proposer's rights are checked again, a transfer clears approvals
+        let proposer_info = MessageInfo {
+            sender: swap.proposer.clone(),
+            funds: vec![],
+        };
+        let mut events = vec![];
+        let mut hooks = vec![];
+        for token_id in swap.offered_tokens.iter() {
+            let (event, token_hooks) = self.transfer_with_hooks(
+                deps.branch(),
+                &env,
+                &proposer_info,
+                swap.counterparty.as_str(),
+                token_id,
+                TokenAction::Transfer,
+            )?;
+            events.push(event);
+            hooks.extend(token_hooks);
+        }
+        for token_id in swap.requested_tokens.iter() {
+            let token = self.tokens.load(deps.storage, token_id)?;
+            if token.owner != swap.counterparty {
+                return Err(ContractError::TokenNotOwned {
+                    token_id: token_id.clone(),
+                    owner: swap.counterparty.to_string(),
+                });
+            }
+            let (event, token_hooks) = self.transfer_with_hooks(
+                deps.branch(),
+                &env,
+                &info,
+                swap.proposer.as_str(),
+                token_id,
+                TokenAction::Transfer,
+            )?;
+            events.push(event);
+            hooks.extend(token_hooks);
+        }
+
+        let messages: Vec<BankMsg> = vec![
+            (swap.counterparty.clone(), swap.offered_funds),
+            (swap.proposer.clone(), swap.requested_funds),
+            (info.sender.clone(), change),
+        ]
+        .into_iter()
+        .filter(|(_, amount)| !amount.is_empty())
+        .map(|(to, amount)| BankMsg::Send {
+            to_address: to.to_string(),
+            amount,
+        })
+        .collect();
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_events(events)
+            .add_submessages(hooks)
+            .add_attribute("action", "accept_swap")
+            .add_attribute("proposer", swap.proposer)
+            .add_attribute("counterparty", swap.counterparty)
+            .add_attribute("swap_id", swap_id.to_string()))
+    }
+
+    fn execute_cancel_swap(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        swap_id: u64,
+    ) -> Result<Response, ContractError> {
+        let swap = self.swaps.load(deps.storage, U64Key::new(swap_id))?;
+        if swap.proposer != info.sender
+            && swap.counterparty != info.sender
+            && !swap.expires.is_expired(&env.block)
+        {
+            return Err(ContractError::Unauthorized {});
+        }
+        self.remove_swap(deps.storage, swap_id, &swap)?;
+
+        let mut res = Response::new();
+        if !swap.offered_funds.is_empty() {
+            res = res.add_message(BankMsg::Send {
+                to_address: swap.proposer.to_string(),
+                amount: swap.offered_funds,
+            });
+        }
+        Ok(res
+            .add_attribute("action", "cancel_swap")
+            .add_attribute("sender", info.sender)
+            .add_attribute("proposer", swap.proposer)
+            .add_attribute("swap_id", swap_id.to_string()))
+    }
+
+    fn execute_set_transfer_lock(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        token_ids: Vec<String>,
+        lock: Option<TransferLock>,
+    ) -> Result<Response, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::NotMinter {});
+        }
+        for token_id in token_ids.iter() {
+            // only uploaded tokens waiting for their owner can be changed
+            let token = self.tokens.load(deps.storage, token_id)?;
+            if token.owner != "not_yet_set" {
+                return Err(ContractError::Claimed {});
+            }
+            match &lock {
+                Some(lock) => self.token_locks.save(deps.storage, token_id, lock)?,
+                None => self.token_locks.remove(deps.storage, token_id),
+            }
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "set_transfer_lock")
+            .add_attribute("token_ids", token_ids.join(","))
+            .add_attribute("locked", lock.is_some().to_string()))
+    }
+
+    fn execute_set_series_lock(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        series: String,
+        series_lock: Option<SeriesLock>,
+    ) -> Result<Response, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::NotMinter {});
+        }
+        match &series_lock {
+            Some(series_lock) => {
+                if series_lock.first_token == 0 || series_lock.first_token > series_lock.last_token
+                {
+                    return Err(ContractError::InvalidSeries {});
+                }
+                self.series_locks.save(deps.storage, &series, series_lock)?
+            }
+            None => self.series_locks.remove(deps.storage, &series),
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "set_series_lock")
+            .add_attribute("series", series)
+            .add_attribute("locked", series_lock.is_some().to_string()))
+    }
+
+    fn execute_burn(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+    ) -> Result<Response, ContractError> {
+        let token = self.tokens.load(deps.storage, &token_id)?;
+        match self.token_locks.may_load(deps.storage, &token_id)? {
+            Some(lock) => {
+                if !lock.burnable {
+                    return Err(ContractError::TransferLocked { token_id });
+                }
+                if token.owner != info.sender {
+                    return Err(ContractError::Unauthorized {});
+                }
+                if self.stakes.may_load(deps.storage, &token_id)?.is_some() {
+                    return Err(ContractError::TokenStaked { token_id });
+                }
+            }
+            None => self.check_can_send(deps.as_ref(), &env, &info, &token_id, &token)?,
+        }
+
+        let mut msgs: Vec<SubMsg> = self
+            .settle_rental(deps.storage, &env.block, &token_id)?
+            .into_iter()
+            .map(SubMsg::new)
+            .collect();
+        msgs.extend(
+            self.cancel_token_orders(deps.storage, &token_id)?
+                .into_iter()
+                .map(SubMsg::new),
+        );
+        msgs.extend(self.transfer_hook_msgs(
+            deps.storage,
+            &token_id,
+            Some(&token.owner),
+            None,
+            TokenAction::Burn,
+        )?);
+        self.tokens.remove(deps.storage, &token_id)?;
+        self.record_ownership(
+            deps.storage,
+            &env,
+            &token_id,
+            Some(&token.owner),
+            None,
+            TokenAction::Burn,
+        )?;
+        self.update_trait_index(deps.storage, &token_id, &token.extension, false)?;
+        let burned = self
+            .burned_count
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        self.burned_count.save(deps.storage, &(burned + 1))?;
+        self.token_locks.remove(deps.storage, &token_id);
+        self.listings.remove(deps.storage, &token_id)?;
+        self.rental_listings.remove(deps.storage, &token_id);
+        self.token_users.remove(deps.storage, &token_id)?;
+
+        Ok(Response::new()
+            .add_event(burn_event(&token_id, &info.sender, &token.owner))
+            .add_submessages(msgs)
+            .add_attribute("action", "burn")
+            .add_attribute("sender", info.sender)
+            .add_attribute("token_id", token_id))
+    }
+
+    fn execute_set_operator_filter(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        filter: OperatorFilter,
+    ) -> Result<Response, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::NotMinter {});
+        }
+        self.operator_filter.save(deps.storage, &filter)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_operator_filter")
+            .add_attribute("filter", format!("{:?}", filter).to_lowercase()))
+    }
+
+    fn execute_set_operator_status(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        operators: Vec<String>,
+        status: Option<OperatorStatus>,
+    ) -> Result<Response, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::NotMinter {});
+        }
+        for operator in operators.iter() {
+            let operator_addr = deps.api.addr_validate(operator)?;
+            match status {
+                Some(status) => {
+                    self.operator_registry
+                        .save(deps.storage, &operator_addr, &status)?
+                }
+                None => self.operator_registry.remove(deps.storage, &operator_addr),
+            }
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "set_operator_status")
+            .add_attribute("operators", operators.join(","))
+            .add_attribute(
+                "status",
+                status.map_or("none".to_string(), |s| format!("{:?}", s).to_lowercase()),
+            ))
+    }
+
+    fn execute_approve_with_permit(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        permit: ApprovalPermit,
+        signature: Binary,
+    ) -> Result<Response, ContractError> {
+        let owner = deps.api.addr_validate(&permit.owner)?;
+        if permit.pubkey.len() != 33 {
+            return Err(ContractError::InvalidPermitKey {});
+        }
+        if permit.signer()? != owner.as_str() {
+            return Err(ContractError::PermitSignerMismatch {
+                owner: owner.to_string(),
+            });
+        }
+        if permit.expires.is_expired(&env.block) {
+            return Err(ContractError::PermitExpired {});
+        }
+        let nonce = self
+            .permit_nonces
+            .may_load(deps.storage, &owner)?
+            .unwrap_or_default();
+        if permit.nonce != nonce {
+            return Err(ContractError::InvalidPermitNonce { expected: nonce });
+        }
+        let message = permit.message(&env.block.chain_id, &env.contract.address)?;
+        if !SignerKey::Secp256k1(permit.pubkey.clone()).verify(deps.api, &message, &signature)? {
+            return Err(ContractError::InvalidSignature {});
+        }
+        self.permit_nonces
+            .save(deps.storage, &owner, &(nonce + 1))?;
+
+        // the permit acts as if the owner sent the approval
+        let owner_info = MessageInfo {
+            sender: owner.clone(),
+            funds: vec![],
+        };
+        let event = match &permit.token_id {
+            Some(token_id) => {
+                self._update_approvals(
+                    deps.branch(),
+                    &env,
+                    &owner_info,
+                    &permit.spender,
+                    token_id,
+                    true,
+                    Some(permit.expires),
+                )?;
+                approve_event(token_id, &owner, &permit.spender, &permit.expires)
+            }
+            None => {
+                let spender = deps.api.addr_validate(&permit.spender)?;
+                self.check_operator_allowed(deps.as_ref(), &spender)?;
+                self.save_operator(deps.storage, &owner, &spender, &permit.expires)?;
+                approve_all_event(&owner, &permit.spender, &permit.expires)
+            }
+        };
+
+        Ok(Response::new()
+            .add_event(event)
+            .add_attribute("action", "approve_with_permit")
+            .add_attribute("sender", info.sender)
+            .add_attribute("owner", owner)
+            .add_attribute("spender", permit.spender)
+            .add_attribute("nonce", nonce.to_string()))
+    }
+
+    fn execute_revoke_all_operators(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        let operators = self
+            .operators
+            .prefix(&info.sender)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .map(|k| String::from_utf8(k).map(Addr::unchecked))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(StdError::invalid_utf8)?;
+        for operator in operators.iter() {
+            self.remove_operator(deps.storage, &info.sender, operator);
+        }
+
+        Ok(Response::new()
+            .add_events(
+                operators
+                    .iter()
+                    .map(|operator| revoke_all_event(&info.sender, operator.as_str())),
+            )
+            .add_attribute("action", "revoke_all_operators")
+            .add_attribute("sender", info.sender)
+            .add_attribute("revoked", operators.len().to_string()))
+    }
+
+    fn execute_prune_operator_grants(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        _info: MessageInfo,
+        operator: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_addr = maybe_addr(deps.api, start_after)?;
+        let start = start_addr.map(|addr| Bound::exclusive(addr.as_ref()));
+        let operator_addr = deps.api.addr_validate(&operator)?;
+        // the limit bounds the grants scanned, live ones included
+        let scanned = self
+            .operator_grants
+            .prefix(&operator_addr)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.and_then(|(k, expires)| Ok((Addr::unchecked(String::from_utf8(k)?), expires)))
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut pruned = 0;
+        for (owner, expires) in scanned.iter() {
+            if expires.is_expired(&env.block) {
+                self.remove_operator(deps.storage, owner, &operator_addr);
+                pruned += 1;
+            }
+        }
+
+        let mut res = Response::new()
+            .add_attribute("action", "prune_operator_grants")
+            .add_attribute("operator", operator)
+            .add_attribute("pruned", pruned.to_string());
+        // where the next call should start from
+        if let Some((owner, _)) = scanned.last() {
+            res = res.add_attribute("last_owner", owner);
+        }
+        Ok(res)
+    }
+
+    fn execute_take_snapshot(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::NotMinter {});
+        }
+        self.take_snapshot(deps.storage, env.block.height)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "take_snapshot")
+            .add_attribute("height", env.block.height.to_string()))
+    }
+
+    fn execute_set_snapshot_interval(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        interval: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::NotMinter {});
+        }
+        match interval {
+            Some(0) => return Err(ContractError::InvalidSnapshotInterval {}),
+            Some(interval) => self.snapshot_interval.save(deps.storage, &interval)?,
+            None => self.snapshot_interval.remove(deps.storage),
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "set_snapshot_interval")
+            .add_attribute(
+                "interval",
+                interval.map_or("none".to_string(), |i| i.to_string()),
+            ))
+    }
+
+    fn execute_set_history_retention(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        limit: u32,
+    ) -> Result<Response, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::NotMinter {});
+        }
+        if limit == 0 {
+            return Err(ContractError::InvalidHistoryRetention {});
+        }
+        // longer histories are trimmed on their next record
+        self.history_retention.save(deps.storage, &limit)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_history_retention")
+            .add_attribute("limit", limit.to_string()))
+    }
+
+    fn execute_set_metadata_rules(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        rules: MetadataRules,
+    ) -> Result<Response, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::NotMinter {});
+        }
+        // only checked on the next metadata write
+        self.metadata_rules.save(deps.storage, &rules)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_metadata_rules")
+            .add_attribute("required_trait_types", rules.required_trait_types.join(",")))
+    }
+
+    fn execute_finalize_upload(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        rarity_method: RarityMethod,
+    ) -> Result<Response, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::NotMinter {});
+        }
+        if self.rarity_method.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::UploadFinalized {});
+        }
+
+        // the collection is capped at MAX_TOKENS, loading every token stays bounded
+        let mut tokens = self
+            .tokens
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.and_then(|(k, token)| Ok((String::from_utf8(k)?, token))))
+            .collect::<StdResult<Vec<_>>>()?;
+        let supply = tokens.len() as u64;
+
+        // a trait repeated in the metadata of a token is counted once
+        let token_traits = |token: &TokenInfo<Extension>| {
+            token
+                .extension
+                .iter()
+                .flat_map(|meta| meta.attributes.iter().flatten())
+                .map(|attr| (attr.trait_type.clone(), attr.value.clone()))
+                .collect::<BTreeSet<_>>()
+        };
+        let mut trait_counts: BTreeMap<(String, String), u64> = BTreeMap::new();
+        for (_, token) in tokens.iter() {
+            for key in token_traits(token) {
+                *trait_counts.entry(key).or_default() += 1;
+            }
+        }
+
+        for ((trait_type, value), count) in trait_counts.iter() {
+            self.upload_trait_counts
+                .save(deps.storage, (trait_type, value), count)?;
+        }
+        self.upload_supply.save(deps.storage, &supply)?;
+
+        let mut scores = Vec::with_capacity(tokens.len());
+        for (token_id, token) in tokens.iter_mut() {
+            let counts: Vec<u64> = token_traits(token)
+                .iter()
+                .map(|key| trait_counts[key])
+                .collect();
+            scores.push((token_id.clone(), rarity_method.score(supply, &counts)));
+
+            // unminted metadata stays hidden, it is filled in at mint
+            if token.owner != "not_yet_set" && self.fill_trait_stats(deps.storage, token)? {
+                self.tokens.save(deps.storage, token_id, token)?;
             }
-            ExecuteMsg::Execute { proposal_id } => {
-                self.execute_execute(deps, env, info, proposal_id)
+        }
+
+        // rarest first, ties go to the lowest token id
+        scores.sort_by(|(a_id, a), (b_id, b)| {
+            b.cmp(a)
+                .then_with(|| (a_id.len(), a_id.as_str()).cmp(&(b_id.len(), b_id.as_str())))
+        });
+        for (i, (token_id, score)) in scores.into_iter().enumerate() {
+            let rank = i as u64 + 1;
+            self.rarity
+                .save(deps.storage, &token_id, &Rarity { score, rank })?;
+            self.rarity_ranking
+                .save(deps.storage, U64Key::new(rank), &token_id)?;
+        }
+        self.rarity_method.save(deps.storage, &rarity_method)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "finalize_upload")
+            .add_attribute("supply", supply.to_string())
+            .add_attribute("traits", trait_counts.len().to_string()))
+    }
+
+    fn execute_set_transfer_hook(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        contract: String,
+        failure: Option<HookFailure>,
+    ) -> Result<Response, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::NotMinter {});
+        }
+        let contract_addr = deps.api.addr_validate(&contract)?;
+        match failure {
+            Some(failure) => self
+                .transfer_hooks
+                .save(deps.storage, &contract_addr, &failure)?,
+            None => self.transfer_hooks.remove(deps.storage, &contract_addr),
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "set_transfer_hook")
+            .add_attribute("contract", contract)
+            .add_attribute(
+                "failure",
+                failure.map_or("none".to_string(), |f| format!("{:?}", f).to_lowercase()),
+            ))
+    }
+
+    fn execute_batch_transfer_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        recipient: String,
+        token_ids: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        let (events, hooks) = self.batch_transfer_nft(
+            deps,
+            &env,
+            &info,
+            &recipient,
+            &token_ids,
+            TokenAction::Transfer,
+        )?;
+
+        Ok(Response::new()
+            .add_events(events)
+            .add_submessages(hooks)
+            .add_attribute("action", "batch_transfer_nft")
+            .add_attribute("sender", info.sender)
+            .add_attribute("recipient", recipient)
+            .add_attribute("token_ids", token_ids.join(",")))
+    }
+
+    fn execute_batch_send_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        token_ids: Vec<String>,
+        msg: Binary,
+    ) -> Result<Response, ContractError> {
+        let (events, hooks) =
+            self.batch_transfer_nft(deps, &env, &info, &contract, &token_ids, TokenAction::Send)?;
+
+        let send = Cw721BatchReceiveMsg {
+            sender: info.sender.to_string(),
+            token_ids: token_ids.clone(),
+            msg,
+        };
+
+        Ok(Response::new()
+            .add_message(send.into_cosmos_msg(contract.clone())?)
+            .add_events(events)
+            .add_submessages(hooks)
+            .add_attribute("action", "batch_send_nft")
+            .add_attribute("sender", info.sender)
+            .add_attribute("recipient", contract)
+            .add_attribute("token_ids", token_ids.join(",")))
+    }
+
+    fn execute_set_user(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        user: Option<String>,
+        expires: Option<Expiration>,
+    ) -> Result<Response, ContractError> {
+        let token = self.tokens.load(deps.storage, &token_id)?;
+        self.check_can_approve(deps.as_ref(), &env, &info, &token)?;
+        if let Some(current) = self.token_users.may_load(deps.storage, &token_id)? {
+            if current.rented && !current.expires.is_expired(&env.block) {
+                return Err(ContractError::RentalActive { token_id });
             }
-            ExecuteMsg::Close { proposal_id } => self.execute_close(deps, env, info, proposal_id),
+        }
 
-            _ => Cw721ExtendedContract::default()._execute(deps, env, info, msg),
+        let res = Response::new()
+            .add_attribute("action", "set_user")
+            .add_attribute("sender", info.sender)
+            .add_attribute("token_id", token_id.clone());
+        match user {
+            Some(user) => {
+                // reject expired data as invalid
+                let expires = expires.unwrap_or_default();
+                if expires.is_expired(&env.block) {
+                    return Err(ContractError::Expired {});
+                }
+                let token_user = TokenUser {
+                    user: deps.api.addr_validate(&user)?,
+                    expires,
+                    rented: false,
+                };
+                self.token_users
+                    .save(deps.storage, &token_id, &token_user)?;
+                Ok(res.add_attribute("user", user))
+            }
+            None => {
+                self.token_users.remove(deps.storage, &token_id)?;
+                Ok(res)
+            }
         }
     }
-}
 
-impl<'a> Cw721ExtendedExecute<Extension> for Cw721ExtendedContract<'a> {
-    fn execute_withdraw(
+    fn execute_list_for_rent(
         &self,
         deps: DepsMut,
         env: Env,
-        _info: MessageInfo,
+        info: MessageInfo,
+        token_id: String,
+        price: Uint128,
+        denom: String,
+        duration: u64,
     ) -> Result<Response, ContractError> {
-        //     let team_signed = self
-        //         .cw3_signature
-        //         .may_load(deps.storage, &Addr::unchecked(ADDR_TEAM))?
-        //         .unwrap_or(false);
-        //     let pro_signed = self
-        //         .cw3_signature
-        //         .may_load(deps.storage, &Addr::unchecked(ADDR_PRO))?
-        //         .unwrap_or(false);
-        //     let treas_signed = self
-        //         .cw3_signature
-        //         .may_load(deps.storage, &Addr::unchecked(ADDR_TREAS))?
-        //         .unwrap_or(false);
+        let token = self.tokens.load(deps.storage, &token_id)?;
+        self.check_can_approve(deps.as_ref(), &env, &info, &token)?;
 
-        //     if !team_signed || !pro_signed || !treas_signed {
-        //         return Err(ContractError::NotAllSigned {});
-        //     }
+        let listing = RentalListing {
+            owner: token.owner,
+            price,
+            denom,
+            duration,
+        };
+        self.rental_listings
+            .save(deps.storage, &token_id, &listing)?;
 
-        let current_uluna_amount = deps
-            .querier
-            .query_balance(env.contract.address.to_string(), "uluna")?
-            .amount;
+        Ok(Response::new()
+            .add_attribute("action", "list_for_rent")
+            .add_attribute("sender", info.sender)
+            .add_attribute("token_id", token_id)
+            .add_attribute("price", listing.price)
+            .add_attribute("denom", listing.denom)
+            .add_attribute("duration", duration.to_string()))
+    }
 
-        let team_portion = current_uluna_amount * Uint128::from(30u128) / Uint128::from(100u128);
-        let pro_portion = current_uluna_amount * Uint128::from(14u128) / Uint128::from(100u128);
-        let treas_portion = current_uluna_amount * Uint128::from(56u128) / Uint128::from(100u128);
+    fn execute_cancel_rental_listing(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+    ) -> Result<Response, ContractError> {
+        let token = self.tokens.load(deps.storage, &token_id)?;
+        self.check_can_approve(deps.as_ref(), &env, &info, &token)?;
+        self.rental_listings.load(deps.storage, &token_id)?;
+        self.rental_listings.remove(deps.storage, &token_id);
 
-        // self.cw3_signature
-        //     .save(deps.storage, &Addr::unchecked(ADDR_TEAM), &(false))?;
-        // self.cw3_signature
-        //     .save(deps.storage, &Addr::unchecked(ADDR_PRO), &(false))?;
-        // self.cw3_signature
-        //     .save(deps.storage, &Addr::unchecked(ADDR_TREAS), &(false))?;
+        Ok(Response::new()
+            .add_attribute("action", "cancel_rental_listing")
+            .add_attribute("sender", info.sender)
+            .add_attribute("token_id", token_id))
+    }
 
-        let mut messages: Vec<CosmosMsg> = vec![];
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: ADDR_TEAM.to_string(),
-            amount: vec![Coin {
-                denom: String::from("uluna"),
-                amount: team_portion,
-            }],
-        }));
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: ADDR_PRO.to_string(),
-            amount: vec![Coin {
-                denom: String::from("uluna"),
-                amount: pro_portion,
-            }],
-        }));
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: ADDR_TREAS.to_string(),
-            amount: vec![Coin {
-                denom: String::from("uluna"),
-                amount: treas_portion,
-            }],
-        }));
-        Ok(Response::new().add_messages(messages))
+    fn execute_rent(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+    ) -> Result<Response, ContractError> {
+        let listing = self.rental_listings.load(deps.storage, &token_id)?;
+        if listing.owner == info.sender {
+            return Err(ContractError::CannotRentOwnToken {});
+        }
+        if let Some(current) = self.token_users.may_load(deps.storage, &token_id)? {
+            if current.rented && !current.expires.is_expired(&env.block) {
+                return Err(ContractError::RentalActive { token_id });
+            }
+        }
+        let change = must_pay_at_least(&info.funds, &listing.denom, listing.price)?;
+
+        // pays out the previous, expired rental
+        let mut messages = self.settle_rental(deps.storage, &env.block, &token_id)?;
+        let end = env.block.time.plus_seconds(listing.duration);
+        let token_user = TokenUser {
+            user: info.sender.clone(),
+            expires: Expiration::AtTime(end),
+            rented: true,
+        };
+        self.token_users
+            .save(deps.storage, &token_id, &token_user)?;
+        // the rent stays in escrow and is released as it is earned
+        let rental = Rental {
+            owner: listing.owner.clone(),
+            renter: info.sender.clone(),
+            price: listing.price,
+            denom: listing.denom.clone(),
+            start: env.block.time,
+            end,
+            paid: Uint128::zero(),
+        };
+        self.rentals.save(deps.storage, &token_id, &rental)?;
+        self.add_escrow(deps.storage, &rental.denom, rental.price)?;
+        messages.extend(refund_msg(&info.sender, &listing.denom, change));
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_attribute("action", "rent")
+            .add_attribute("user", info.sender)
+            .add_attribute("owner", listing.owner)
+            .add_attribute("token_id", token_id)
+            .add_attribute("expires", token_user.expires.to_string()))
+    }
+
+    fn execute_claim_rent(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+    ) -> Result<Response, ContractError> {
+        let mut rental = self.rentals.load(deps.storage, &token_id)?;
+        let earned = rental.earned(env.block.time);
+        let amount = earned - rental.paid;
+        if amount.is_zero() {
+            return Err(ContractError::NothingToClaim {});
+        }
+        self.release_escrow(deps.storage, &rental.denom, amount)?;
+        if earned == rental.price {
+            self.rentals.remove(deps.storage, &token_id);
+        } else {
+            rental.paid = earned;
+            self.rentals.save(deps.storage, &token_id, &rental)?;
+        }
+
+        Ok(Response::new()
+            .add_messages(refund_msg(&rental.owner, &rental.denom, amount))
+            .add_attribute("action", "claim_rent")
+            .add_attribute("sender", info.sender)
+            .add_attribute("owner", rental.owner)
+            .add_attribute("token_id", token_id)
+            .add_attribute("amount", amount))
+    }
+
+    fn execute_set_staking_config(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        config: StakingConfig,
+    ) -> Result<Response, ContractError> {
+        let minter = self.minter.load(deps.storage)?;
+        if info.sender != minter {
+            return Err(ContractError::NotMinter {});
+        }
+        if let StakingReward::Cw20 { address } = &config.reward {
+            deps.api.addr_validate(address.as_str())?;
+        }
+        self.staking_config.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_staking_config")
+            .add_attribute("enabled", config.enabled.to_string())
+            .add_attribute("reward_rate", config.reward_rate))
+    }
+
+    fn execute_stake(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_ids: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        let config = self
+            .staking_config
+            .may_load(deps.storage)?
+            .filter(|config| config.enabled)
+            .ok_or(ContractError::StakingDisabled {})?;
+
+        for token_id in token_ids.iter() {
+            let token = self.tokens.load(deps.storage, token_id)?;
+            if token.owner != info.sender {
+                return Err(ContractError::Unauthorized {});
+            }
+            if self.stakes.may_load(deps.storage, token_id)?.is_some() {
+                return Err(ContractError::TokenStaked {
+                    token_id: token_id.clone(),
+                });
+            }
+            let attributes = token
+                .extension
+                .as_ref()
+                .and_then(|meta| meta.attributes.clone())
+                .unwrap_or_default();
+            let stake = StakeInfo {
+                owner: info.sender.clone(),
+                staked_at: env.block.time,
+                last_claim: env.block.time,
+                rate: config.rate_for(&attributes),
+            };
+            self.stakes.save(deps.storage, token_id, &stake)?;
+            // a staked token cannot be sold
+            self.listings.remove(deps.storage, token_id)?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("action", "stake")
+            .add_attribute("owner", info.sender)
+            .add_attribute("token_ids", token_ids.join(",")))
+    }
+
+    fn execute_unstake(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_ids: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        let res = self.claim_staking_rewards(deps, &env, &info.sender, &token_ids, true)?;
+        Ok(res
+            .add_attribute("action", "unstake")
+            .add_attribute("owner", info.sender)
+            .add_attribute("token_ids", token_ids.join(",")))
+    }
+
+    fn execute_claim_staking_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_ids: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        let res = self.claim_staking_rewards(deps, &env, &info.sender, &token_ids, false)?;
+        Ok(res
+            .add_attribute("action", "claim_staking_rewards")
+            .add_attribute("owner", info.sender)
+            .add_attribute("token_ids", token_ids.join(",")))
     }
 
     // fn execute_set_art_reveal(
@@ -256,6 +2170,16 @@ impl<'a> Cw721ExtendedExecute<Extension> for Cw721ExtendedContract<'a> {
         if info.sender != self.minter.load(deps.storage)? {
             return Err(ContractError::NotMinter {});
         }
+        if self.rarity_method.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::UploadFinalized {});
+        }
+        if let Some(meta) = &ext {
+            let rules = self
+                .metadata_rules
+                .may_load(deps.storage)?
+                .unwrap_or_default();
+            validate_metadata(meta, &rules)?;
+        }
         let token = TokenInfo {
             owner: Addr::unchecked("not_yet_set"),
             approvals: vec![],
@@ -271,9 +2195,11 @@ impl<'a> Cw721ExtendedExecute<Extension> for Cw721ExtendedContract<'a> {
                 },
                 None => Ok(token),
             })?;
+        let image = ext.and_then(|meta| meta.image);
         Ok(Response::new()
-            .add_attribute("action", &format!("add extension for TOKEN #{}", token_id))
-            .add_attribute("extension.image", &ext.unwrap().image.unwrap()))
+            .add_event(metadata_event(&token_id, &info.sender, image.as_deref()))
+            .add_attribute("action", "add_extension")
+            .add_attribute("token_id", token_id))
     }
 
     fn execute_propose(
@@ -331,10 +2257,10 @@ impl<'a> Cw721ExtendedExecute<Extension> for Cw721ExtendedContract<'a> {
             .save(deps.storage, (&id.to_string()[..], &info.sender), &ballot)?;
 
         Ok(Response::new()
+            .add_event(proposal_event("propose", id, &info.sender, prop.status))
             .add_attribute("action", "propose")
             .add_attribute("sender", info.sender)
-            .add_attribute("proposal_id", id.to_string())
-            .add_attribute("status", format!("{:?}", prop.status)))
+            .add_attribute("proposal_id", id.to_string()))
     }
 
     fn execute_vote(
@@ -383,10 +2309,15 @@ impl<'a> Cw721ExtendedExecute<Extension> for Cw721ExtendedContract<'a> {
             .save(deps.storage, &proposal_id.to_string()[..], &prop)?;
 
         Ok(Response::new()
+            .add_event(proposal_event(
+                "vote",
+                proposal_id,
+                &info.sender,
+                prop.status,
+            ))
             .add_attribute("action", "vote")
             .add_attribute("sender", info.sender)
-            .add_attribute("proposal_id", proposal_id.to_string())
-            .add_attribute("status", format!("{:?}", prop.status)))
+            .add_attribute("proposal_id", proposal_id.to_string()))
     }
 
     fn execute_execute(
@@ -415,6 +2346,12 @@ impl<'a> Cw721ExtendedExecute<Extension> for Cw721ExtendedContract<'a> {
         // dispatch all proposed messages
         Ok(Response::new()
             .add_messages(prop.msgs)
+            .add_event(proposal_event(
+                "execute",
+                proposal_id,
+                &info.sender,
+                prop.status,
+            ))
             .add_attribute("action", "execute")
             .add_attribute("sender", info.sender)
             .add_attribute("proposal_id", proposal_id.to_string()))
@@ -448,8 +2385,233 @@ impl<'a> Cw721ExtendedExecute<Extension> for Cw721ExtendedContract<'a> {
             .save(deps.storage, &proposal_id.to_string()[..], &prop)?;
 
         Ok(Response::new()
+            .add_event(proposal_event(
+                "close",
+                proposal_id,
+                &info.sender,
+                prop.status,
+            ))
             .add_attribute("action", "close")
             .add_attribute("sender", info.sender)
             .add_attribute("proposal_id", proposal_id.to_string()))
     }
 }
+
+impl<'a> Cw721ExtendedContract<'a> {
+    /// Removes the offers on a token and the swaps listing it, refunding what they escrow
+    fn cancel_token_orders(
+        &self,
+        storage: &mut dyn Storage,
+        token_id: &str,
+    ) -> StdResult<Vec<BankMsg>> {
+        let target = OfferTarget::Token {
+            token_id: token_id.to_string(),
+        };
+        let offers = self
+            .offers
+            .idx
+            .target
+            .prefix(target.key())
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut msgs = vec![];
+        for (k, offer) in offers {
+            self.offers.remove(storage, U64Key::from(k))?;
+            self.release_escrow(storage, &offer.denom, offer.amount)?;
+            msgs.extend(refund_msg(&offer.bidder, &offer.denom, offer.amount));
+        }
+
+        let swap_ids = self
+            .swap_tokens
+            .prefix(token_id)
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(k, _)| k))
+            .collect::<StdResult<Vec<_>>>()?;
+        for k in swap_ids {
+            let id = u64::from_be_bytes(
+                k.as_slice()
+                    .try_into()
+                    .map_err(|_| StdError::generic_err("Invalid u64 key"))?,
+            );
+            let swap = self.swaps.load(storage, U64Key::new(id))?;
+            self.remove_swap(storage, id, &swap)?;
+            if !swap.offered_funds.is_empty() {
+                msgs.push(BankMsg::Send {
+                    to_address: swap.proposer.to_string(),
+                    amount: swap.offered_funds,
+                });
+            }
+        }
+        Ok(msgs)
+    }
+
+    /// mints the next token and indexes its traits, which only become public at mint
+    fn mint_and_index(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: MintMsg<Extension>,
+    ) -> Result<Response, ContractError> {
+        let token_id = (self.token_count(deps.storage)? + 1).to_string();
+        let res = self.mint(deps.branch(), env, info, msg)?;
+        let mut token = self.tokens.load(deps.storage, &token_id)?;
+        if self.fill_trait_stats(deps.storage, &mut token)? {
+            self.tokens.save(deps.storage, &token_id, &token)?;
+        }
+        self.update_trait_index(deps.storage, &token_id, &token.extension, true)?;
+        Ok(res)
+    }
+
+    /// writes the upload-wide count and frequency into the token attributes,
+    /// returns false before the upload is finalized or without attributes
+    fn fill_trait_stats(
+        &self,
+        storage: &dyn Storage,
+        token: &mut TokenInfo<Extension>,
+    ) -> StdResult<bool> {
+        let supply = match self.upload_supply.may_load(storage)? {
+            Some(supply) => supply,
+            None => return Ok(false),
+        };
+        let attributes = match token
+            .extension
+            .as_mut()
+            .and_then(|meta| meta.attributes.as_mut())
+        {
+            Some(attributes) => attributes,
+            None => return Ok(false),
+        };
+        for attr in attributes.iter_mut() {
+            let count = self
+                .upload_trait_counts
+                .load(storage, (&attr.trait_type, &attr.value))?;
+            attr.count = count.to_string();
+            attr.frequency = Decimal::from_ratio(count, supply).to_string();
+        }
+        Ok(true)
+    }
+
+    /// adds or removes a token under every trait of its metadata
+    fn update_trait_index(
+        &self,
+        storage: &mut dyn Storage,
+        token_id: &str,
+        extension: &Extension,
+        add: bool,
+    ) -> StdResult<()> {
+        let attributes = match extension.as_ref().and_then(|meta| meta.attributes.as_ref()) {
+            Some(attributes) => attributes,
+            None => return Ok(()),
+        };
+        for attr in attributes.iter() {
+            let key = (attr.trait_type.as_str(), attr.value.as_str(), token_id);
+            // a trait repeated in the metadata is counted once
+            if self.trait_tokens.may_load(storage, key)?.is_some() == add {
+                continue;
+            }
+            let count_key = (attr.trait_type.as_str(), attr.value.as_str());
+            let count = self
+                .trait_counts
+                .may_load(storage, count_key)?
+                .unwrap_or_default();
+            if add {
+                self.trait_tokens.save(storage, key, &Empty {})?;
+                self.trait_counts.save(storage, count_key, &(count + 1))?;
+            } else {
+                self.trait_tokens.remove(storage, key);
+                match count {
+                    0 | 1 => self.trait_counts.remove(storage, count_key),
+                    _ => self.trait_counts.save(storage, count_key, &(count - 1))?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// transfers every token or none, each one is checked with `check_can_send`
+    fn batch_transfer_nft(
+        &self,
+        mut deps: DepsMut,
+        env: &Env,
+        info: &MessageInfo,
+        recipient: &str,
+        token_ids: &[String],
+        action: TokenAction,
+    ) -> Result<(Vec<Event>, Vec<SubMsg>), ContractError> {
+        if token_ids.is_empty() {
+            return Err(ContractError::EmptyBatch {});
+        }
+        let mut events = vec![];
+        let mut hooks = vec![];
+        for (i, token_id) in token_ids.iter().enumerate() {
+            if token_ids[..i].contains(token_id) {
+                return Err(ContractError::DuplicateToken {
+                    token_id: token_id.clone(),
+                });
+            }
+            let (event, token_hooks) =
+                self.transfer_with_hooks(deps.branch(), env, info, recipient, token_id, action)?;
+            events.push(event);
+            hooks.extend(token_hooks);
+        }
+        Ok((events, hooks))
+    }
+
+    /// pays out the rewards accrued by `token_ids`, releasing them if `unstake`
+    fn claim_staking_rewards(
+        &self,
+        deps: DepsMut,
+        env: &Env,
+        owner: &Addr,
+        token_ids: &[String],
+        unstake: bool,
+    ) -> Result<Response, ContractError> {
+        let mut reward = Uint128::zero();
+        for token_id in token_ids.iter() {
+            let mut stake = self
+                .stakes
+                .may_load(deps.storage, token_id)?
+                .ok_or_else(|| ContractError::NotStaked {
+                    token_id: token_id.clone(),
+                })?;
+            if stake.owner != *owner {
+                return Err(ContractError::Unauthorized {});
+            }
+            reward += stake.pending(env.block.time);
+            if unstake {
+                self.stakes.remove(deps.storage, token_id)?;
+            } else {
+                stake.last_claim = env.block.time;
+                self.stakes.save(deps.storage, token_id, &stake)?;
+            }
+        }
+
+        let res = Response::new().add_attribute("reward", reward);
+        if reward.is_zero() {
+            return Ok(res);
+        }
+        // disabling staking stops new stakes, accrued rewards stay claimable
+        let config = self
+            .staking_config
+            .may_load(deps.storage)?
+            .ok_or(ContractError::StakingDisabled {})?;
+        match config.reward {
+            StakingReward::Points {} => {
+                self.staking_points
+                    .update(deps.storage, owner, |points| -> StdResult<_> {
+                        Ok(points.unwrap_or_default() + reward)
+                    })?;
+                Ok(res)
+            }
+            StakingReward::Cw20 { address } => Ok(res.add_message(WasmMsg::Execute {
+                contract_addr: address.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: owner.to_string(),
+                    amount: reward,
+                })?,
+                funds: vec![],
+            })),
+        }
+    }
+}
@@ -1,17 +1,23 @@
 mod constants;
+#[cfg(test)]
+mod contract_tests;
 mod error;
+mod events;
 mod execute;
 mod execute2;
 pub mod msg;
+mod payment;
 mod query;
 mod query2;
 pub mod state;
 pub mod state2;
 mod threshold;
 mod traits;
+mod validation;
 
 pub use crate::constants::*;
 pub use crate::error::ContractError;
+pub use crate::events::*;
 pub use crate::msg::*;
 pub use crate::state::*;
 pub use crate::threshold::*;
@@ -23,7 +29,7 @@ pub mod entry {
     use super::*;
 
     use cosmwasm_std::entry_point;
-    use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+    use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult};
 
     // This makes a conscious choice on the various generics used by the contract
     #[entry_point]
@@ -54,6 +60,12 @@ pub mod entry {
         tract.execute(deps, env, info, msg)
     }
 
+    #[entry_point]
+    pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+        let tract = Cw721ExtendedContract::default();
+        tract.reply(deps, env, msg)
+    }
+
     #[entry_point]
     pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         let tract = Cw721ExtendedContract::default();
@@ -1,8 +1,16 @@
+use crate::state2::{
+    ApprovalPermit, Auction, AuctionState, DutchAuction, HookFailure, Listing, MetadataRules,
+    MintVoucher, Offer, OfferTarget, OperatorFilter, OperatorStatus, OwnershipRecord, Rarity,
+    RarityMethod, ReferralConfig, ReferralStats, Rental, RentalListing, SaleConfig, SeriesLock,
+    SignerKey, StakeInfo, StakingConfig, Swap, TokenUser, TransferLock,
+};
 use crate::threshold::ThresholdResponse;
-use cosmwasm_std::{Binary, CosmosMsg, Empty, Uint128};
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Coin, CosmosMsg, Empty, StdResult, Timestamp, Uint128, WasmMsg,
+};
 use cw0::{Duration, Expiration};
 use cw3::Vote;
-use cw721::CustomMsg;
+use cw721::{AllNftInfoResponse, CustomMsg, NftInfoResponse, OwnerOfResponse};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -93,6 +101,256 @@ pub enum ExecuteMsg<T> {
         base_uri: String,
     },
 
+    // Set or clear the dutch auction sale stage
+    SetDutchAuction {
+        auction: Option<DutchAuction>,
+    },
+
+    // Claim back what was paid above the dutch auction clearing price, the latest auction by default
+    ClaimRebate {
+        auction_id: Option<u64>,
+    },
+
+    // Set or clear the public key mint vouchers must be signed with
+    SetVoucherSigner {
+        signer: Option<SignerKey>,
+    },
+
+    // Configure or switch the referral program on and off
+    SetReferralConfig {
+        config: ReferralConfig,
+    },
+
+    // Send the sender's unclaimed referral commission
+    ClaimReferralCommission {},
+
+    // Set the anti-bot mint protections
+    SetSaleConfig {
+        config: SaleConfig,
+    },
+
+    // List a token for sale at a fixed price, by its owner or an approved operator
+    ListNft {
+        token_id: String,
+        price: Uint128,
+        denom: String,
+        expires: Option<Expiration>,
+    },
+
+    // Remove a listing
+    CancelListing {
+        token_id: String,
+    },
+
+    // Buy a listed token, paying royalties and the seller
+    BuyNft {
+        token_id: String,
+    },
+
+    // Escrow a token into an english auction
+    CreateAuction {
+        token_id: String,
+        denom: String,
+        reserve_price: Uint128,
+        min_increment: Uint128,
+        /// seconds until the auction ends
+        duration: u64,
+        /// late bids push the end back by this many seconds
+        extension: u64,
+    },
+
+    // Bid the sent funds, refunding the previous highest bidder
+    PlaceBid {
+        auction_id: u64,
+    },
+
+    // Distribute token and funds once the auction ended
+    SettleAuction {
+        auction_id: u64,
+    },
+
+    // Return the token to the seller, only before any bid
+    CancelAuction {
+        auction_id: u64,
+    },
+
+    // Escrow the sent funds as an offer on a token, trait or the whole collection
+    MakeOffer {
+        target: OfferTarget,
+        denom: String,
+        expires: Option<Expiration>,
+    },
+
+    // Sell a matching token into an offer, by its owner or an approved operator
+    AcceptOffer {
+        offer_id: u64,
+        token_id: String,
+    },
+
+    // Refund an offer, by the bidder or by anyone once expired
+    CancelOffer {
+        offer_id: u64,
+    },
+
+    // Propose to exchange tokens and the sent funds for tokens and funds of a counterparty
+    ProposeSwap {
+        counterparty: String,
+        offered_tokens: Vec<String>,
+        requested_tokens: Vec<String>,
+        requested_funds: Vec<Coin>,
+        expires: Option<Expiration>,
+    },
+
+    // Execute a swap atomically, by the counterparty
+    AcceptSwap {
+        swap_id: u64,
+    },
+
+    // Drop a swap and refund the escrowed funds, by either party or by anyone once expired
+    CancelSwap {
+        swap_id: u64,
+    },
+
+    // Preset the transfer lock of tokens before they are minted, only by the minter
+    SetTransferLock {
+        token_ids: Vec<String>,
+        lock: Option<TransferLock>,
+    },
+
+    // Lock every token of a range at mint, only by the minter
+    SetSeriesLock {
+        series: String,
+        series_lock: Option<SeriesLock>,
+    },
+
+    // Destroy a token, locked tokens only when their lock allows it
+    Burn {
+        token_id: String,
+    },
+
+    // Choose how the operator registry is enforced, only by the minter
+    SetOperatorFilter {
+        filter: OperatorFilter,
+    },
+
+    // Allow or block operator contracts, None removes them from the registry
+    SetOperatorStatus {
+        operators: Vec<String>,
+        status: Option<OperatorStatus>,
+    },
+
+    // Apply an approval signed off-chain by the token owner, callable by anyone
+    ApproveWithPermit {
+        permit: ApprovalPermit,
+        /// Signature over the sha256 of `ApprovalPermit::message`
+        signature: Binary,
+    },
+
+    // Remove every ApproveAll permission granted by the sender
+    RevokeAllOperators {},
+
+    // Scan up to `limit` grants to an operator after `start_after` and remove
+    // the expired ones, callable by anyone
+    PruneOperatorGrants {
+        operator: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Record the owners at the end of the current block, only by the minter
+    TakeSnapshot {},
+
+    // Take a snapshot automatically every `interval` blocks, only by the minter
+    SetSnapshotInterval {
+        interval: Option<u64>,
+    },
+
+    // Checks applied to uploaded metadata, only by the minter
+    SetMetadataRules {
+        rules: MetadataRules,
+    },
+
+    // Close the metadata upload, fill in the trait counts and frequencies and
+    // rank every token by rarity, only by the minter
+    FinalizeUpload {
+        rarity_method: RarityMethod,
+    },
+
+    // Cap the ownership records kept per token, at least one, only by the minter
+    SetHistoryRetention {
+        limit: u32,
+    },
+
+    // Register a contract notified of every token movement, None removes it
+    SetTransferHook {
+        contract: String,
+        failure: Option<HookFailure>,
+    },
+
+    // Transfer several tokens at once, failing as a whole if any cannot be sent
+    BatchTransferNft {
+        recipient: String,
+        token_ids: Vec<String>,
+    },
+
+    // Send several tokens to a contract with a single `BatchReceiveNft` callback
+    BatchSendNft {
+        contract: String,
+        token_ids: Vec<String>,
+        msg: Binary,
+    },
+
+    // Grant or clear the user role of a token, by its owner or an operator
+    SetUser {
+        token_id: String,
+        user: Option<String>,
+        expires: Option<Expiration>,
+    },
+
+    // Offer a token for rent, the renter becomes its user for `duration` seconds
+    ListForRent {
+        token_id: String,
+        price: Uint128,
+        denom: String,
+        duration: u64,
+    },
+
+    // Withdraw a rental listing
+    CancelRentalListing {
+        token_id: String,
+    },
+
+    // Pay the rental price and become the user of a token, the rent is held
+    // in escrow and the unused part refunded if the token changes hands
+    Rent {
+        token_id: String,
+    },
+
+    // Pay the owner the rent earned so far, callable by anyone
+    ClaimRent {
+        token_id: String,
+    },
+
+    // Set the staking rates and reward, only by the minter
+    SetStakingConfig {
+        config: StakingConfig,
+    },
+
+    // Lock owned tokens to accrue rewards, they cannot be sent while staked
+    Stake {
+        token_ids: Vec<String>,
+    },
+
+    // Claim the rewards and release the tokens
+    Unstake {
+        token_ids: Vec<String>,
+    },
+
+    // Claim the rewards accrued by staked tokens
+    ClaimStakingRewards {
+        token_ids: Vec<String>,
+    },
+
     // Set Art reveal.
     // SetArtReveal {
     //     art_reveal: bool,
@@ -148,6 +406,98 @@ pub struct MintMsg<T> {
     pub token_uri: Option<String>,
     /// Any custom extension used by this contract
     pub extension: T,
+    /// Required once a voucher signer is set
+    pub voucher: Option<SignedVoucher>,
+    /// Earns a commission on this mint while the referral program is enabled
+    pub referrer: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SignedVoucher {
+    pub voucher: MintVoucher,
+    /// Signature over `MintVoucher::message`
+    pub signature: Binary,
+}
+
+/// Cw721BatchReceiveMsg should be de/serialized under `BatchReceiveNft()` variant in a ExecuteMsg
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw721BatchReceiveMsg {
+    pub sender: String,
+    pub token_ids: Vec<String>,
+    pub msg: Binary,
+}
+
+impl Cw721BatchReceiveMsg {
+    /// serializes the message
+    pub fn into_binary(self) -> StdResult<Binary> {
+        let msg = BatchReceiverExecuteMsg::BatchReceiveNft(self);
+        to_binary(&msg)
+    }
+
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>, C>(self, contract_addr: T) -> StdResult<CosmosMsg<C>>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        let msg = self.into_binary()?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+/// This is just a helper to properly serialize the above message.
+/// The actual receiver should include this variant in the larger ExecuteMsg enum
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+enum BatchReceiverExecuteMsg {
+    BatchReceiveNft(Cw721BatchReceiveMsg),
+}
+
+/// Notification sent to transfer hooks under the `TransferHook()` variant of their ExecuteMsg
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct TransferHookMsg {
+    pub token_id: String,
+    /// None on mint
+    pub from: Option<String>,
+    /// None on burn
+    pub to: Option<String>,
+    /// mint, transfer, send or burn
+    pub action: String,
+}
+
+impl TransferHookMsg {
+    /// serializes the message
+    pub fn into_binary(self) -> StdResult<Binary> {
+        let msg = TransferHookExecuteMsg::TransferHook(self);
+        to_binary(&msg)
+    }
+
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>, C>(self, contract_addr: T) -> StdResult<CosmosMsg<C>>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        let msg = self.into_binary()?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+/// This is just a helper to properly serialize the above message.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+enum TransferHookExecuteMsg {
+    TransferHook(TransferHookMsg),
 }
 
 // #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -185,6 +535,15 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// List all owners that granted ApproveAll to the given operator
+    /// Return type: `OperatorGrantsResponse`
+    OperatorGrants {
+        operator: String,
+        /// unset or false will filter out expired items, you must set to true to see them
+        include_expired: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     /// Total number of tokens issued
     NumTokens {},
 
@@ -221,6 +580,24 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// With Enumerable extension.
+    /// Same pagination as `Tokens`, with the `AllNftInfo` of every token.
+    /// Return type: TokensWithInfoResponse.
+    TokensWithInfo {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// `OwnerOf` for up to MAX_LIMIT tokens, expired approvals filtered out.
+    /// Return type: BatchOwnerOfResponse.
+    BatchOwnerOf {
+        token_ids: Vec<String>,
+    },
+    /// `NftInfo` for up to MAX_LIMIT tokens.
+    /// Return type: BatchNftInfoResponse.
+    BatchNftInfo {
+        token_ids: Vec<String>,
+    },
 
     // Return the minter
     Minter {},
@@ -233,7 +610,7 @@ pub enum QueryMsg {
     //     token_id: String,
     // },
 
-    // Get wallet balance
+    // Get the number of tokens held by a wallet
     GetBalance {
         owner: String,
     },
@@ -256,6 +633,243 @@ pub enum QueryMsg {
     },
     CheckRoyalties {},
 
+    // Dutch auction stage with its current price
+    DutchAuction {},
+
+    // Public key mint vouchers are checked against
+    VoucherSigner {},
+
+    // Number of mints already done with a voucher nonce
+    VoucherNonce {
+        nonce: u64,
+    },
+
+    // Referral program config
+    ReferralConfig {},
+
+    // Referral stats of a single referrer
+    ReferralStats {
+        referrer: String,
+    },
+
+    // Referrers with the most referred mints first
+    ReferralLeaderboard {
+        limit: Option<u32>,
+    },
+
+    // Anti-bot mint protections
+    SaleConfig {},
+
+    // Marketplace listing of a token
+    Listing {
+        token_id: String,
+    },
+
+    // Active listings of a seller
+    ListingsBySeller {
+        seller: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Active listings in a denom, cheapest first
+    ListingsByPrice {
+        denom: String,
+        min_price: Option<Uint128>,
+        max_price: Option<Uint128>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // All active listings
+    AllListings {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Auction details
+    Auction {
+        auction_id: u64,
+    },
+
+    // Auctions in a given state
+    AuctionsByState {
+        state: AuctionState,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    // Offer details
+    Offer {
+        offer_id: u64,
+    },
+
+    // Offers on a token, trait or the whole collection
+    OffersByTarget {
+        target: OfferTarget,
+        include_expired: Option<bool>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    // Offers made by a bidder
+    OffersByBidder {
+        bidder: String,
+        include_expired: Option<bool>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    // Operator registry and how it is enforced
+    OperatorRegistry {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Next permit nonce of an owner
+    PermitNonce {
+        owner: String,
+    },
+
+    // Heights at which owners were recorded
+    Snapshots {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    // Owner of a token at a snapshot height
+    OwnerOfAtHeight {
+        token_id: String,
+        height: u64,
+    },
+
+    // Tokens held by an owner at a snapshot height
+    // Return type: TokensResponse.
+    TokensOfAtHeight {
+        owner: String,
+        height: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Minted tokens having a trait, with the number of such tokens
+    TokensByTrait {
+        trait_type: String,
+        value: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Values of a trait type among minted tokens, with their token counts
+    TraitValues {
+        trait_type: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Addresses holding at least one token with their holding count
+    Holders {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Checks applied to uploaded metadata
+    MetadataRules {},
+
+    // Rarity score and rank of a token, once the upload is finalized
+    Rarity {
+        token_id: String,
+    },
+
+    // Tokens from the rarest, `start_after` is a rank
+    RarityRanking {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    // Supply, holders, sale stage and revenue of the collection
+    CollectionStats {},
+
+    // Ownership changes of a token, oldest first
+    TokenHistory {
+        token_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    // Contracts notified of token movements
+    TransferHooks {},
+
+    // Current user of a token
+    UserOf {
+        token_id: String,
+    },
+
+    // Tokens currently used by an address
+    // Return type: TokensResponse.
+    TokensUsedBy {
+        user: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Rental listing of a token
+    RentalListing {
+        token_id: String,
+    },
+
+    // Rent held in escrow for a token and the part the owner can claim
+    Rental {
+        token_id: String,
+    },
+
+    // Transfer lock of a token
+    TransferLock {
+        token_id: String,
+    },
+
+    // Locks applied to token ranges at mint
+    SeriesLocks {},
+
+    // Staking rates and reward
+    StakingConfig {},
+
+    // Stake of a token with its pending reward
+    Stake {
+        token_id: String,
+    },
+
+    // Stakes of an owner with their pending rewards
+    StakesByOwner {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    // Reward points credited to an address
+    StakingPoints {
+        address: String,
+    },
+
+    // Swap details
+    Swap {
+        swap_id: u64,
+    },
+
+    // Swaps proposed by an address
+    SwapsByProposer {
+        proposer: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    // Swaps awaiting an address
+    SwapsByCounterparty {
+        counterparty: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
     ///////////////////////////////
     /////    CW3 multisig    //////
     ///////////////////////////////
@@ -307,6 +921,52 @@ pub struct MinterResponse {
     pub minter: String,
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OperatorGrant {
+    pub owner: String,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OperatorGrantsResponse {
+    pub grants: Vec<OperatorGrant>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TokenWithInfo<T> {
+    pub token_id: String,
+    pub info: AllNftInfoResponse<T>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TokensWithInfoResponse<T> {
+    pub tokens: Vec<TokenWithInfo<T>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TokenOwner {
+    pub token_id: String,
+    pub owner: OwnerOfResponse,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BatchOwnerOfResponse {
+    /// In the order of the requested token ids
+    pub owners: Vec<TokenOwner>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TokenNftInfo<T> {
+    pub token_id: String,
+    pub info: NftInfoResponse<T>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BatchNftInfoResponse<T> {
+    /// In the order of the requested token ids
+    pub nfts: Vec<TokenNftInfo<T>>,
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct RoyaltiesInfoResponse {
     pub address: String,
@@ -318,6 +978,252 @@ pub struct CheckRoyaltiesResponse {
     pub royalty_payments: bool,
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DutchAuctionResponse {
+    /// Id of the latest auction, to claim its rebates with
+    pub auction_id: u64,
+    pub auction: Option<DutchAuction>,
+    pub current_price: Option<Uint128>,
+    pub next_price_drop: Option<Timestamp>,
+    /// Set once the auction has ended
+    pub clearing_price: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct VoucherSignerResponse {
+    pub signer: Option<SignerKey>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct VoucherNonceResponse {
+    pub used: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PermitNonceResponse {
+    pub nonce: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ReferralStatsResponse {
+    pub referrer: String,
+    pub stats: ReferralStats,
+    pub claimable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ReferralLeaderboardResponse {
+    pub referrers: Vec<ReferralStatsResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ListingResponse {
+    pub listing: Option<Listing>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ListingsResponse {
+    pub listings: Vec<Listing>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AuctionResponse {
+    pub auction_id: u64,
+    pub auction: Auction,
+    /// Open auctions past their end time wait for settlement
+    pub ended: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AuctionsResponse {
+    pub auctions: Vec<AuctionResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OfferResponse {
+    pub offer_id: u64,
+    pub offer: Offer,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OffersResponse {
+    pub offers: Vec<OfferResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SwapResponse {
+    pub swap_id: u64,
+    pub swap: Swap,
+    pub expired: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SwapsResponse {
+    pub swaps: Vec<SwapResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OperatorRegistryResponse {
+    pub filter: OperatorFilter,
+    pub operators: Vec<(Addr, OperatorStatus)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SnapshotsResponse {
+    pub interval: Option<u64>,
+    pub heights: Vec<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OwnerOfAtHeightResponse {
+    /// None if the token was not minted yet or burned
+    pub owner: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TokensByTraitResponse {
+    /// all tokens with the trait, not only this page
+    pub count: u64,
+    pub tokens: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TraitValueCount {
+    pub value: String,
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TraitValuesResponse {
+    pub values: Vec<TraitValueCount>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MetadataRulesResponse {
+    pub rules: MetadataRules,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RarityResponse {
+    pub token_id: String,
+    pub rarity: Rarity,
+    pub method: RarityMethod,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RankedToken {
+    pub token_id: String,
+    pub rarity: Rarity,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RarityRankingResponse {
+    pub method: RarityMethod,
+    pub tokens: Vec<RankedToken>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Holder {
+    pub address: Addr,
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct HoldersResponse {
+    pub holders: Vec<Holder>,
+}
+
+/// How the next public mint is priced
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SaleStage {
+    /// every mint needs a voucher, priced by the voucher
+    Voucher,
+    /// dutch auction set but not started
+    AuctionPending,
+    DutchAuction,
+    /// fixed MINT_PRICE
+    Public,
+    SoldOut,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CollectionStatsResponse {
+    pub minted: u64,
+    pub burned: u64,
+    /// tokens left to mint
+    pub remaining: u64,
+    pub holders: u64,
+    pub stage: SaleStage,
+    /// None when the price is not known in advance
+    pub price: Option<Uint128>,
+    pub denom: String,
+    pub revenue: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TokenHistoryResponse {
+    pub history: Vec<OwnershipRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TransferHooksResponse {
+    pub hooks: Vec<(Addr, HookFailure)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct UserOfResponse {
+    pub token_id: String,
+    /// None once the role has expired
+    pub user: Option<TokenUser>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RentalListingResponse {
+    pub listing: Option<RentalListing>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RentalResponse {
+    pub rental: Option<Rental>,
+    pub claimable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TransferLockResponse {
+    pub token_id: String,
+    pub lock: Option<TransferLock>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SeriesLocksResponse {
+    pub series: Vec<(String, SeriesLock)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StakingConfigResponse {
+    pub config: Option<StakingConfig>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StakeResponse {
+    pub token_id: String,
+    pub stake: StakeInfo,
+    pub pending: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StakesResponse {
+    pub stakes: Vec<StakeResponse>,
+    /// sum of the pending rewards of the returned stakes
+    pub pending: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StakingPointsResponse {
+    pub points: Uint128,
+}
+
 // #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 // pub struct IsOnRevealResponse {
 //     pub is_on_reveal: bool,
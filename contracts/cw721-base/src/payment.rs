@@ -0,0 +1,220 @@
+use cosmwasm_std::{Addr, BankMsg, Coin, Decimal, Uint128};
+
+use crate::constants::{ROYALTY_ADDRESS, ROYALTY_PERCENTAGE};
+use crate::error::ContractError;
+
+/// Royalty recipient and amount owed on a sale at `sale_price`
+pub fn royalty_payment(sale_price: Uint128) -> (Addr, Uint128) {
+    let percentage = Decimal::percent(ROYALTY_PERCENTAGE);
+    (Addr::unchecked(ROYALTY_ADDRESS), sale_price * percentage)
+}
+
+/// Checks that `funds` only hold `denom` and cover `price`, returning the change
+/// that has to be refunded to the sender.
+pub fn must_pay_at_least(
+    funds: &[Coin],
+    denom: &str,
+    price: Uint128,
+) -> Result<Uint128, ContractError> {
+    let mut received = Uint128::zero();
+    for coin in funds.iter().filter(|coin| !coin.amount.is_zero()) {
+        if coin.denom != denom {
+            return Err(ContractError::UnexpectedDenom {
+                denom: coin.denom.clone(),
+            });
+        }
+        received += coin.amount;
+    }
+    if received < price {
+        return Err(ContractError::InsufficientFunds {
+            denom: denom.to_string(),
+            expected: price,
+            received,
+        });
+    }
+    Ok(received - price)
+}
+
+/// Checks that `funds` cover every coin in `required` and nothing else,
+/// returning the change per denom that has to be refunded to the sender.
+/// `required` must hold each denom once, see `merge_coins`.
+pub fn must_pay_coins(funds: &[Coin], required: &[Coin]) -> Result<Vec<Coin>, ContractError> {
+    for (i, coin) in required.iter().enumerate() {
+        if required[..i].iter().any(|r| r.denom == coin.denom) {
+            return Err(ContractError::DuplicateDenom {
+                denom: coin.denom.clone(),
+            });
+        }
+    }
+    if let Some(coin) = funds
+        .iter()
+        .find(|coin| !coin.amount.is_zero() && !required.iter().any(|r| r.denom == coin.denom))
+    {
+        return Err(ContractError::UnexpectedDenom {
+            denom: coin.denom.clone(),
+        });
+    }
+    let mut change = vec![];
+    for coin in required.iter() {
+        let received: Uint128 = funds
+            .iter()
+            .filter(|f| f.denom == coin.denom)
+            .map(|f| f.amount)
+            .sum();
+        if received < coin.amount {
+            return Err(ContractError::InsufficientFunds {
+                denom: coin.denom.clone(),
+                expected: coin.amount,
+                received,
+            });
+        }
+        if received > coin.amount {
+            change.push(Coin {
+                denom: coin.denom.clone(),
+                amount: received - coin.amount,
+            });
+        }
+    }
+    Ok(change)
+}
+
+/// Adds up coins of the same denom and drops zero amounts, keeping the first order
+pub fn merge_coins(coins: Vec<Coin>) -> Vec<Coin> {
+    let mut merged: Vec<Coin> = vec![];
+    for coin in coins.into_iter().filter(|coin| !coin.amount.is_zero()) {
+        match merged.iter_mut().find(|m| m.denom == coin.denom) {
+            Some(m) => m.amount += coin.amount,
+            None => merged.push(coin),
+        }
+    }
+    merged
+}
+
+/// Bank transfer of `amount` back to `recipient`, None if there is nothing to send
+pub fn refund_msg(recipient: &Addr, denom: &str, amount: Uint128) -> Option<BankMsg> {
+    if amount.is_zero() {
+        return None;
+    }
+    Some(BankMsg::Send {
+        to_address: recipient.to_string(),
+        amount: vec![Coin {
+            denom: denom.to_string(),
+            amount,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::coin;
+
+    #[test]
+    fn payment_change() {
+        let price = Uint128::new(100);
+        assert_eq!(
+            must_pay_at_least(&[coin(100, "uusd")], "uusd", price),
+            Ok(Uint128::zero())
+        );
+        assert_eq!(
+            must_pay_at_least(&[coin(60, "uusd"), coin(70, "uusd")], "uusd", price),
+            Ok(Uint128::new(30))
+        );
+        // zero amounts of other denoms are ignored
+        assert_eq!(
+            must_pay_at_least(&[coin(100, "uusd"), coin(0, "uluna")], "uusd", price),
+            Ok(Uint128::zero())
+        );
+    }
+
+    #[test]
+    fn payment_errors() {
+        let price = Uint128::new(100);
+        assert_eq!(
+            must_pay_at_least(&[coin(99, "uusd")], "uusd", price),
+            Err(ContractError::InsufficientFunds {
+                denom: "uusd".to_string(),
+                expected: price,
+                received: Uint128::new(99),
+            })
+        );
+        assert_eq!(
+            must_pay_at_least(&[], "uusd", price),
+            Err(ContractError::InsufficientFunds {
+                denom: "uusd".to_string(),
+                expected: price,
+                received: Uint128::zero(),
+            })
+        );
+        assert_eq!(
+            must_pay_at_least(&[coin(100, "uusd"), coin(5, "uluna")], "uusd", price),
+            Err(ContractError::UnexpectedDenom {
+                denom: "uluna".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn pay_coins() {
+        let required = vec![coin(100, "uusd"), coin(5, "uluna")];
+        assert_eq!(
+            must_pay_coins(&[coin(5, "uluna"), coin(120, "uusd")], &required),
+            Ok(vec![coin(20, "uusd")])
+        );
+        assert_eq!(
+            must_pay_coins(&[coin(100, "uusd")], &required),
+            Err(ContractError::InsufficientFunds {
+                denom: "uluna".to_string(),
+                expected: Uint128::new(5),
+                received: Uint128::zero(),
+            })
+        );
+        assert_eq!(
+            must_pay_coins(&[coin(1, "ukrw")], &[]),
+            Err(ContractError::UnexpectedDenom {
+                denom: "ukrw".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn pay_duplicate_denoms() {
+        // a single payment must not cover a denom required twice
+        let required = vec![coin(1000, "uusd"), coin(1000, "uusd")];
+        assert_eq!(
+            must_pay_coins(&[coin(1000, "uusd")], &required),
+            Err(ContractError::DuplicateDenom {
+                denom: "uusd".to_string()
+            })
+        );
+        assert_eq!(
+            merge_coins(vec![
+                coin(1000, "uusd"),
+                coin(0, "ukrw"),
+                coin(5, "uluna"),
+                coin(1000, "uusd")
+            ]),
+            vec![coin(2000, "uusd"), coin(5, "uluna")]
+        );
+    }
+
+    #[test]
+    fn royalty() {
+        let (addr, amount) = royalty_payment(Uint128::new(1000));
+        assert_eq!(addr, ROYALTY_ADDRESS);
+        assert_eq!(amount, Uint128::new(60));
+    }
+
+    #[test]
+    fn refund() {
+        let addr = Addr::unchecked("buyer");
+        assert_eq!(refund_msg(&addr, "uusd", Uint128::zero()), None);
+        assert_eq!(
+            refund_msg(&addr, "uusd", Uint128::new(5)),
+            Some(BankMsg::Send {
+                to_address: "buyer".to_string(),
+                amount: vec![coin(5, "uusd")],
+            })
+        );
+    }
+}
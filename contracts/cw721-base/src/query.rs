@@ -187,6 +187,92 @@ where
         })
     }
 
+    pub fn operator_grants(
+        &self,
+        deps: Deps,
+        env: Env,
+        operator: String,
+        include_expired: bool,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<OperatorGrantsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_addr = maybe_addr(deps.api, start_after)?;
+        let start = start_addr.map(|addr| Bound::exclusive(addr.as_ref()));
+
+        let operator_addr = deps.api.addr_validate(&operator)?;
+        let grants: StdResult<Vec<_>> = self
+            .operator_grants
+            .prefix(&operator_addr)
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter(|r| {
+                include_expired || r.is_err() || !r.as_ref().unwrap().1.is_expired(&env.block)
+            })
+            .take(limit)
+            .map(|item| {
+                item.and_then(|(k, expires)| {
+                    Ok(OperatorGrant {
+                        owner: String::from_utf8(k)?,
+                        expires,
+                    })
+                })
+            })
+            .collect();
+        Ok(OperatorGrantsResponse { grants: grants? })
+    }
+
+    pub fn tokens_with_info(
+        &self,
+        deps: Deps,
+        env: Env,
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensWithInfoResponse<T>> {
+        let TokensResponse { tokens } = self.tokens(deps, owner, start_after, limit)?;
+        let tokens = tokens
+            .into_iter()
+            .map(|token_id| {
+                let info = self.all_nft_info(deps, env.clone(), token_id.clone(), false)?;
+                Ok(TokenWithInfo { token_id, info })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(TokensWithInfoResponse { tokens })
+    }
+
+    pub fn batch_owner_of(
+        &self,
+        deps: Deps,
+        env: Env,
+        token_ids: Vec<String>,
+    ) -> StdResult<BatchOwnerOfResponse> {
+        check_batch_size(&token_ids)?;
+        let owners = token_ids
+            .into_iter()
+            .map(|token_id| {
+                let owner = self.owner_of(deps, env.clone(), token_id.clone(), false)?;
+                Ok(TokenOwner { token_id, owner })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(BatchOwnerOfResponse { owners })
+    }
+
+    pub fn batch_nft_info(
+        &self,
+        deps: Deps,
+        token_ids: Vec<String>,
+    ) -> StdResult<BatchNftInfoResponse<T>> {
+        check_batch_size(&token_ids)?;
+        let nfts = token_ids
+            .into_iter()
+            .map(|token_id| {
+                let info = self.nft_info(deps, token_id.clone())?;
+                Ok(TokenNftInfo { token_id, info })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(BatchNftInfoResponse { nfts })
+    }
+
     pub fn _query(&self, deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         match msg {
             QueryMsg::Minter {} => to_binary(&self.minter(deps)?),
@@ -220,6 +306,19 @@ where
                 start_after,
                 limit,
             )?),
+            QueryMsg::OperatorGrants {
+                operator,
+                include_expired,
+                start_after,
+                limit,
+            } => to_binary(&self.operator_grants(
+                deps,
+                env,
+                operator,
+                include_expired.unwrap_or(false),
+                start_after,
+                limit,
+            )?),
             QueryMsg::NumTokens {} => to_binary(&self.num_tokens(deps)?),
             QueryMsg::Tokens {
                 owner,
@@ -229,11 +328,33 @@ where
             QueryMsg::AllTokens { start_after, limit } => {
                 to_binary(&self.all_tokens(deps, start_after, limit)?)
             }
+            QueryMsg::TokensWithInfo {
+                owner,
+                start_after,
+                limit,
+            } => to_binary(&self.tokens_with_info(deps, env, owner, start_after, limit)?),
+            QueryMsg::BatchOwnerOf { token_ids } => {
+                to_binary(&self.batch_owner_of(deps, env, token_ids)?)
+            }
+            QueryMsg::BatchNftInfo { token_ids } => {
+                to_binary(&self.batch_nft_info(deps, token_ids)?)
+            }
             _ => panic!("Cannot recognize query"),
         }
     }
 }
 
+/// Batch queries are capped like paginated ones so a single query stays bounded
+fn check_batch_size(token_ids: &[String]) -> StdResult<()> {
+    if token_ids.len() > MAX_LIMIT as usize {
+        return Err(StdError::generic_err(format!(
+            "Cannot query more than {} tokens at once",
+            MAX_LIMIT
+        )));
+    }
+    Ok(())
+}
+
 fn parse_approval(item: StdResult<Pair<Expiration>>) -> StdResult<cw721::Approval> {
     item.and_then(|(k, expires)| {
         let spender = String::from_utf8(k)?;
@@ -1,15 +1,19 @@
 use crate::constants::*;
 use crate::msg::*;
+use crate::payment::royalty_payment;
 use crate::state::*;
 use crate::state2::*;
 use crate::threshold::ThresholdResponse;
 use crate::traits::*;
 // use bytes::Bytes;
 use cosmwasm_std::{
-    to_binary, Addr, Binary, BlockInfo, Decimal, Deps, Env, Order, StdResult, Uint128,
+    to_binary, Addr, Binary, BlockInfo, Deps, Env, Order, StdError, StdResult, Uint128,
 };
 // use cw3;
-use cw_storage_plus::Bound;
+use cw0::maybe_addr;
+use cw721::TokensResponse;
+use cw_storage_plus::{Bound, PrimaryKey, U128Key, U64Key};
+use std::convert::TryInto;
 
 impl<'a> Cw721ExtendedContract<'a> {
     pub fn query(&self, deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
@@ -19,6 +23,175 @@ impl<'a> Cw721ExtendedContract<'a> {
                 sale_price,
             } => to_binary(&self.query_royalties_info(deps, token_id, sale_price)?),
             QueryMsg::CheckRoyalties {} => to_binary(&self.check_royalties(deps)?),
+            QueryMsg::DutchAuction {} => to_binary(&self.query_dutch_auction(deps, env)?),
+            QueryMsg::VoucherSigner {} => to_binary(&self.query_voucher_signer(deps)?),
+            QueryMsg::VoucherNonce { nonce } => to_binary(&self.query_voucher_nonce(deps, nonce)?),
+            QueryMsg::ReferralConfig {} => to_binary(&self.query_referral_config(deps)?),
+            QueryMsg::ReferralStats { referrer } => {
+                to_binary(&self.query_referral_stats(deps, env, referrer)?)
+            }
+            QueryMsg::ReferralLeaderboard { limit } => {
+                to_binary(&self.query_referral_leaderboard(deps, env, limit)?)
+            }
+            QueryMsg::SaleConfig {} => to_binary(&self.query_sale_config(deps)?),
+            QueryMsg::Listing { token_id } => to_binary(&self.query_listing(deps, token_id)?),
+            QueryMsg::ListingsBySeller {
+                seller,
+                start_after,
+                limit,
+            } => {
+                to_binary(&self.query_listings_by_seller(deps, env, seller, start_after, limit)?)
+            }
+            QueryMsg::ListingsByPrice {
+                denom,
+                min_price,
+                max_price,
+                start_after,
+                limit,
+            } => to_binary(&self.query_listings_by_price(
+                deps,
+                env,
+                denom,
+                min_price,
+                max_price,
+                start_after,
+                limit,
+            )?),
+            QueryMsg::AllListings { start_after, limit } => {
+                to_binary(&self.query_all_listings(deps, env, start_after, limit)?)
+            }
+            QueryMsg::Auction { auction_id } => {
+                to_binary(&self.query_auction(deps, env, auction_id)?)
+            }
+            QueryMsg::AuctionsByState {
+                state,
+                start_after,
+                limit,
+            } => to_binary(&self.query_auctions_by_state(deps, env, state, start_after, limit)?),
+            QueryMsg::Offer { offer_id } => to_binary(&self.query_offer(deps, offer_id)?),
+            QueryMsg::OffersByTarget {
+                target,
+                include_expired,
+                start_after,
+                limit,
+            } => to_binary(&self.query_offers_by_target(
+                deps,
+                env,
+                target,
+                include_expired.unwrap_or(false),
+                start_after,
+                limit,
+            )?),
+            QueryMsg::OperatorRegistry { start_after, limit } => {
+                to_binary(&self.query_operator_registry(deps, start_after, limit)?)
+            }
+            QueryMsg::PermitNonce { owner } => to_binary(&self.query_permit_nonce(deps, owner)?),
+            QueryMsg::Snapshots { start_after, limit } => {
+                to_binary(&self.query_snapshots(deps, start_after, limit)?)
+            }
+            QueryMsg::OwnerOfAtHeight { token_id, height } => {
+                to_binary(&self.query_owner_of_at_height(deps, token_id, height)?)
+            }
+            QueryMsg::TokensOfAtHeight {
+                owner,
+                height,
+                start_after,
+                limit,
+            } => to_binary(&self.query_tokens_of_at_height(
+                deps,
+                owner,
+                height,
+                start_after,
+                limit,
+            )?),
+            QueryMsg::TokensByTrait {
+                trait_type,
+                value,
+                start_after,
+                limit,
+            } => to_binary(&self.query_tokens_by_trait(
+                deps,
+                trait_type,
+                value,
+                start_after,
+                limit,
+            )?),
+            QueryMsg::TraitValues {
+                trait_type,
+                start_after,
+                limit,
+            } => to_binary(&self.query_trait_values(deps, trait_type, start_after, limit)?),
+            QueryMsg::Holders { start_after, limit } => {
+                to_binary(&self.query_holders(deps, start_after, limit)?)
+            }
+            QueryMsg::CollectionStats {} => to_binary(&self.query_collection_stats(deps, env)?),
+            QueryMsg::MetadataRules {} => to_binary(&self.query_metadata_rules(deps)?),
+            QueryMsg::Rarity { token_id } => to_binary(&self.query_rarity(deps, token_id)?),
+            QueryMsg::RarityRanking { start_after, limit } => {
+                to_binary(&self.query_rarity_ranking(deps, start_after, limit)?)
+            }
+            QueryMsg::TokenHistory {
+                token_id,
+                start_after,
+                limit,
+            } => to_binary(&self.query_token_history(deps, token_id, start_after, limit)?),
+            QueryMsg::TransferHooks {} => to_binary(&self.query_transfer_hooks(deps)?),
+            QueryMsg::UserOf { token_id } => to_binary(&self.query_user_of(deps, env, token_id)?),
+            QueryMsg::TokensUsedBy {
+                user,
+                start_after,
+                limit,
+            } => to_binary(&self.query_tokens_used_by(deps, env, user, start_after, limit)?),
+            QueryMsg::RentalListing { token_id } => {
+                to_binary(&self.query_rental_listing(deps, token_id)?)
+            }
+            QueryMsg::Rental { token_id } => to_binary(&self.query_rental(deps, env, token_id)?),
+            QueryMsg::TransferLock { token_id } => {
+                to_binary(&self.query_transfer_lock(deps, token_id)?)
+            }
+            QueryMsg::SeriesLocks {} => to_binary(&self.query_series_locks(deps)?),
+            QueryMsg::StakingConfig {} => to_binary(&self.query_staking_config(deps)?),
+            QueryMsg::Stake { token_id } => to_binary(&self.query_stake(deps, env, token_id)?),
+            QueryMsg::StakesByOwner {
+                owner,
+                start_after,
+                limit,
+            } => to_binary(&self.query_stakes_by_owner(deps, env, owner, start_after, limit)?),
+            QueryMsg::StakingPoints { address } => {
+                to_binary(&self.query_staking_points(deps, address)?)
+            }
+            QueryMsg::Swap { swap_id } => to_binary(&self.query_swap(deps, env, swap_id)?),
+            QueryMsg::SwapsByProposer {
+                proposer,
+                start_after,
+                limit,
+            } => {
+                to_binary(&self.query_swaps_by_proposer(deps, env, proposer, start_after, limit)?)
+            }
+            QueryMsg::SwapsByCounterparty {
+                counterparty,
+                start_after,
+                limit,
+            } => to_binary(&self.query_swaps_by_counterparty(
+                deps,
+                env,
+                counterparty,
+                start_after,
+                limit,
+            )?),
+            QueryMsg::OffersByBidder {
+                bidder,
+                include_expired,
+                start_after,
+                limit,
+            } => to_binary(&self.query_offers_by_bidder(
+                deps,
+                env,
+                bidder,
+                include_expired.unwrap_or(false),
+                start_after,
+                limit,
+            )?),
             // QueryMsg::IsOnReveal {} => to_binary(&self.query_is_on_reveal(deps)?),
             // QueryMsg::GetTokenUri { token_id } => {
             //     to_binary(&self.query_get_token_uri(deps, token_id)?)
@@ -66,12 +239,11 @@ impl<'a> Cw721ExtendedQuery<Extension> for Cw721ExtendedContract<'a> {
         _token_id: String,
         sale_price: Uint128,
     ) -> StdResult<RoyaltiesInfoResponse> {
-        let percentage = Decimal::percent(ROYALTY_PERCENTAGE);
-        let royalty_from_sale_price = sale_price * percentage;
+        let (address, royalty_amount) = royalty_payment(sale_price);
 
         Ok(RoyaltiesInfoResponse {
-            address: ROYALTY_ADDRESS.to_string(),
-            royalty_amount: royalty_from_sale_price,
+            address: address.to_string(),
+            royalty_amount,
         })
     }
 
@@ -81,6 +253,786 @@ impl<'a> Cw721ExtendedQuery<Extension> for Cw721ExtendedContract<'a> {
         })
     }
 
+    fn query_dutch_auction(&self, deps: Deps, env: Env) -> StdResult<DutchAuctionResponse> {
+        let auction = self.dutch_auction.may_load(deps.storage)?;
+        let auction_id = self
+            .dutch_auction_id
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        match auction {
+            Some(auction) => Ok(DutchAuctionResponse {
+                auction_id,
+                current_price: Some(auction.price_at(env.block.time)),
+                next_price_drop: auction.next_price_drop(env.block.time),
+                clearing_price: self.dutch_auction_clearing_price(
+                    deps.storage,
+                    &auction,
+                    env.block.time,
+                )?,
+                auction: Some(auction),
+            }),
+            None => Ok(DutchAuctionResponse {
+                auction_id,
+                auction: None,
+                current_price: None,
+                next_price_drop: None,
+                clearing_price: self
+                    .dutch_auction_final_price
+                    .may_load(deps.storage, U64Key::new(auction_id))?,
+            }),
+        }
+    }
+
+    fn query_voucher_signer(&self, deps: Deps) -> StdResult<VoucherSignerResponse> {
+        let signer = self.voucher_signer.may_load(deps.storage)?;
+        Ok(VoucherSignerResponse { signer })
+    }
+
+    fn query_voucher_nonce(&self, deps: Deps, nonce: u64) -> StdResult<VoucherNonceResponse> {
+        let used = self
+            .voucher_nonces
+            .may_load(deps.storage, U64Key::new(nonce))?
+            .unwrap_or_default();
+        Ok(VoucherNonceResponse { used })
+    }
+
+    fn query_referral_config(&self, deps: Deps) -> StdResult<ReferralConfig> {
+        Ok(self
+            .referral_config
+            .may_load(deps.storage)?
+            .unwrap_or_default())
+    }
+
+    fn query_referral_stats(
+        &self,
+        deps: Deps,
+        env: Env,
+        referrer: String,
+    ) -> StdResult<ReferralStatsResponse> {
+        let addr = deps.api.addr_validate(&referrer)?;
+        let (stats, _) = self.settled_referral_stats(deps.storage, &addr, env.block.time)?;
+        Ok(ReferralStatsResponse {
+            referrer,
+            claimable: stats.claimable(),
+            stats,
+        })
+    }
+
+    fn query_referral_leaderboard(
+        &self,
+        deps: Deps,
+        env: Env,
+        limit: Option<u32>,
+    ) -> StdResult<ReferralLeaderboardResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let referrers = self
+            .referrals
+            .idx
+            .referrals
+            .range(deps.storage, None, None, Order::Descending)
+            .take(limit)
+            .map(|item| {
+                let (addr, _) = item?;
+                let referrer = String::from_utf8_lossy(&addr).to_string();
+                let (stats, _) = self.settled_referral_stats(
+                    deps.storage,
+                    &Addr::unchecked(&referrer),
+                    env.block.time,
+                )?;
+                Ok(ReferralStatsResponse {
+                    referrer,
+                    claimable: stats.claimable(),
+                    stats,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(ReferralLeaderboardResponse { referrers })
+    }
+
+    fn query_sale_config(&self, deps: Deps) -> StdResult<SaleConfig> {
+        Ok(self.sale_config.may_load(deps.storage)?.unwrap_or_default())
+    }
+
+    fn query_listing(&self, deps: Deps, token_id: String) -> StdResult<ListingResponse> {
+        let listing = self.listings.may_load(deps.storage, &token_id)?;
+        Ok(ListingResponse { listing })
+    }
+
+    fn query_listings_by_seller(
+        &self,
+        deps: Deps,
+        env: Env,
+        seller: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ListingsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let seller_addr = deps.api.addr_validate(&seller)?;
+        let listings = self
+            .listings
+            .idx
+            .seller
+            .prefix(seller_addr)
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter(|item| !is_expired_listing(&env.block, item))
+            .take(limit)
+            .map(|item| item.map(|(_, listing)| listing))
+            .collect::<StdResult<_>>()?;
+        Ok(ListingsResponse { listings })
+    }
+
+    fn query_listings_by_price(
+        &self,
+        deps: Deps,
+        env: Env,
+        denom: String,
+        min_price: Option<Uint128>,
+        max_price: Option<Uint128>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ListingsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        // bounds within the denom prefix are (price, token_id)
+        let start = match start_after {
+            Some(token_id) => {
+                let after = self.listings.load(deps.storage, &token_id)?;
+                Some(Bound::exclusive(
+                    (U128Key::new(after.price.u128()), token_id.into_bytes()).joined_key(),
+                ))
+            }
+            None => min_price
+                .map(|price| Bound::inclusive((U128Key::new(price.u128()), vec![]).joined_key())),
+        };
+        let end = match max_price.map(|price| price.u128().checked_add(1)) {
+            Some(Some(price)) => Some(Bound::exclusive((U128Key::new(price), vec![]).joined_key())),
+            _ => None,
+        };
+
+        let listings = self
+            .listings
+            .idx
+            .price
+            .sub_prefix(denom.into_bytes())
+            .range(deps.storage, start, end, Order::Ascending)
+            .filter(|item| !is_expired_listing(&env.block, item))
+            .take(limit)
+            .map(|item| item.map(|(_, listing)| listing))
+            .collect::<StdResult<_>>()?;
+        Ok(ListingsResponse { listings })
+    }
+
+    fn query_all_listings(
+        &self,
+        deps: Deps,
+        env: Env,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ListingsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let listings = self
+            .listings
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter(|item| !is_expired_listing(&env.block, item))
+            .take(limit)
+            .map(|item| item.map(|(_, listing)| listing))
+            .collect::<StdResult<_>>()?;
+        Ok(ListingsResponse { listings })
+    }
+
+    fn query_auction(&self, deps: Deps, env: Env, auction_id: u64) -> StdResult<AuctionResponse> {
+        let auction = self.auctions.load(deps.storage, U64Key::new(auction_id))?;
+        Ok(AuctionResponse {
+            auction_id,
+            ended: auction.is_ended(env.block.time),
+            auction,
+        })
+    }
+
+    fn query_auctions_by_state(
+        &self,
+        deps: Deps,
+        env: Env,
+        state: AuctionState,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<AuctionsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive_int);
+
+        let auctions = self
+            .auctions
+            .idx
+            .state
+            .prefix(vec![state as u8])
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.and_then(|(k, auction)| {
+                    Ok(AuctionResponse {
+                        auction_id: parse_u64_key(&k)?,
+                        ended: auction.is_ended(env.block.time),
+                        auction,
+                    })
+                })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(AuctionsResponse { auctions })
+    }
+
+    fn query_offer(&self, deps: Deps, offer_id: u64) -> StdResult<OfferResponse> {
+        let offer = self.offers.load(deps.storage, U64Key::new(offer_id))?;
+        Ok(OfferResponse { offer_id, offer })
+    }
+
+    fn query_offers_by_target(
+        &self,
+        deps: Deps,
+        env: Env,
+        target: OfferTarget,
+        include_expired: bool,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<OffersResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive_int);
+
+        let offers = self
+            .offers
+            .idx
+            .target
+            .prefix(target.key())
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter(|item| include_expired || !is_expired_offer(&env.block, item))
+            .take(limit)
+            .map(parse_offer)
+            .collect::<StdResult<_>>()?;
+        Ok(OffersResponse { offers })
+    }
+
+    fn query_offers_by_bidder(
+        &self,
+        deps: Deps,
+        env: Env,
+        bidder: String,
+        include_expired: bool,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<OffersResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive_int);
+
+        let bidder_addr = deps.api.addr_validate(&bidder)?;
+        let offers = self
+            .offers
+            .idx
+            .bidder
+            .prefix(bidder_addr)
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter(|item| include_expired || !is_expired_offer(&env.block, item))
+            .take(limit)
+            .map(parse_offer)
+            .collect::<StdResult<_>>()?;
+        Ok(OffersResponse { offers })
+    }
+
+    fn query_swap(&self, deps: Deps, env: Env, swap_id: u64) -> StdResult<SwapResponse> {
+        let swap = self.swaps.load(deps.storage, U64Key::new(swap_id))?;
+        Ok(SwapResponse {
+            swap_id,
+            expired: swap.expires.is_expired(&env.block),
+            swap,
+        })
+    }
+
+    fn query_swaps_by_proposer(
+        &self,
+        deps: Deps,
+        env: Env,
+        proposer: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<SwapsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive_int);
+
+        let proposer_addr = deps.api.addr_validate(&proposer)?;
+        let swaps = self
+            .swaps
+            .idx
+            .proposer
+            .prefix(proposer_addr)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| parse_swap(&env.block, item))
+            .collect::<StdResult<_>>()?;
+        Ok(SwapsResponse { swaps })
+    }
+
+    fn query_swaps_by_counterparty(
+        &self,
+        deps: Deps,
+        env: Env,
+        counterparty: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<SwapsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive_int);
+
+        let counterparty_addr = deps.api.addr_validate(&counterparty)?;
+        let swaps = self
+            .swaps
+            .idx
+            .counterparty
+            .prefix(counterparty_addr)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| parse_swap(&env.block, item))
+            .collect::<StdResult<_>>()?;
+        Ok(SwapsResponse { swaps })
+    }
+
+    fn query_operator_registry(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<OperatorRegistryResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_addr = maybe_addr(deps.api, start_after)?;
+        let start = start_addr.map(|addr| Bound::exclusive(addr.as_ref()));
+
+        let filter = self
+            .operator_filter
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        let operators = self
+            .operator_registry
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.and_then(|(k, status)| Ok((Addr::unchecked(String::from_utf8(k)?), status)))
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(OperatorRegistryResponse { filter, operators })
+    }
+
+    fn query_permit_nonce(&self, deps: Deps, owner: String) -> StdResult<PermitNonceResponse> {
+        let owner_addr = deps.api.addr_validate(&owner)?;
+        Ok(PermitNonceResponse {
+            nonce: self
+                .permit_nonces
+                .may_load(deps.storage, &owner_addr)?
+                .unwrap_or_default(),
+        })
+    }
+
+    fn query_snapshots(
+        &self,
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<SnapshotsResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive_int);
+
+        let interval = self.snapshot_interval.may_load(deps.storage)?;
+        let heights = self
+            .snapshots
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|k| parse_u64_key(&k))
+            .collect::<StdResult<_>>()?;
+        Ok(SnapshotsResponse { interval, heights })
+    }
+
+    fn query_owner_of_at_height(
+        &self,
+        deps: Deps,
+        token_id: String,
+        height: u64,
+    ) -> StdResult<OwnerOfAtHeightResponse> {
+        let owner = self.owner_at_height(deps.storage, &token_id, height)?;
+        Ok(OwnerOfAtHeightResponse { owner })
+    }
+
+    fn query_tokens_of_at_height(
+        &self,
+        deps: Deps,
+        owner: String,
+        height: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = match start_after {
+            Some(token_id) => {
+                token_id
+                    .parse::<u64>()
+                    .map_err(|_| StdError::generic_err(format!("Invalid token id {}", token_id)))?
+                    + 1
+            }
+            None => 1,
+        };
+
+        let owner_addr = deps.api.addr_validate(&owner)?;
+        self.assert_snapshot(deps.storage, height)?;
+        // every token ever minted, including the ones burned since the snapshot,
+        // the collection is capped at MAX_TOKENS so the scan stays bounded
+        let mut tokens = vec![];
+        for token_num in start..=self.token_count(deps.storage)? {
+            let token_id = token_num.to_string();
+            if self.owner_at_height(deps.storage, &token_id, height)? == Some(owner_addr.clone()) {
+                tokens.push(token_id);
+                if tokens.len() == limit {
+                    break;
+                }
+            }
+        }
+        Ok(TokensResponse { tokens })
+    }
+
+    fn query_tokens_by_trait(
+        &self,
+        deps: Deps,
+        trait_type: String,
+        value: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensByTraitResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let count = self
+            .trait_counts
+            .may_load(deps.storage, (&trait_type, &value))?
+            .unwrap_or_default();
+        let tokens = self
+            .trait_tokens
+            .prefix((&trait_type, &value))
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|k| String::from_utf8(k).map_err(StdError::invalid_utf8))
+            .collect::<StdResult<_>>()?;
+        Ok(TokensByTraitResponse { count, tokens })
+    }
+
+    fn query_trait_values(
+        &self,
+        deps: Deps,
+        trait_type: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TraitValuesResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let values = self
+            .trait_counts
+            .prefix(&trait_type)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.and_then(|(k, count)| {
+                    Ok(TraitValueCount {
+                        value: String::from_utf8(k)?,
+                        count,
+                    })
+                })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(TraitValuesResponse { values })
+    }
+
+    fn query_holders(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<HoldersResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_addr = maybe_addr(deps.api, start_after)?;
+        let start = start_addr.map(|addr| Bound::exclusive(addr.as_ref()));
+
+        let holders = self
+            .holders
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.and_then(|(k, count)| {
+                    Ok(Holder {
+                        address: Addr::unchecked(String::from_utf8(k)?),
+                        count,
+                    })
+                })
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(HoldersResponse { holders })
+    }
+
+    fn query_collection_stats(&self, deps: Deps, env: Env) -> StdResult<CollectionStatsResponse> {
+        let minted = self.token_count(deps.storage)?;
+        // same precedence as mint: voucher, then dutch auction, then the fixed price
+        let (stage, price) = if minted >= MAX_TOKENS {
+            (SaleStage::SoldOut, None)
+        } else if self.voucher_signer.may_load(deps.storage)?.is_some() {
+            (SaleStage::Voucher, None)
+        } else if let Some(auction) = self.dutch_auction.may_load(deps.storage)? {
+            if auction.is_started(env.block.time) {
+                (
+                    SaleStage::DutchAuction,
+                    Some(auction.price_at(env.block.time)),
+                )
+            } else {
+                (SaleStage::AuctionPending, Some(auction.start_price))
+            }
+        } else {
+            (SaleStage::Public, Some(Uint128::from(MINT_PRICE)))
+        };
+
+        Ok(CollectionStatsResponse {
+            minted,
+            burned: self
+                .burned_count
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+            remaining: MAX_TOKENS.saturating_sub(minted),
+            holders: self
+                .holder_count
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+            stage,
+            price,
+            denom: String::from(MINT_DENOM),
+            revenue: self
+                .mint_revenue
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+        })
+    }
+
+    fn query_metadata_rules(&self, deps: Deps) -> StdResult<MetadataRulesResponse> {
+        let rules = self
+            .metadata_rules
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        Ok(MetadataRulesResponse { rules })
+    }
+
+    fn query_rarity(&self, deps: Deps, token_id: String) -> StdResult<RarityResponse> {
+        let method = self.rarity_method.load(deps.storage)?;
+        // like the metadata, rarity is hidden until the token is minted
+        if self.tokens.load(deps.storage, &token_id)?.owner == "not_yet_set" {
+            return Err(StdError::generic_err(format!(
+                "Token {} is not minted yet",
+                token_id
+            )));
+        }
+        let rarity = self.rarity.load(deps.storage, &token_id)?;
+        Ok(RarityResponse {
+            token_id,
+            rarity,
+            method,
+        })
+    }
+
+    fn query_rarity_ranking(
+        &self,
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<RarityRankingResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive_int);
+
+        let method = self.rarity_method.load(deps.storage)?;
+        // ranks are over the whole upload but only minted tokens are listed
+        let tokens = self
+            .rarity_ranking
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter_map(|item| {
+                let token_id = match item {
+                    Ok((_, token_id)) => token_id,
+                    Err(err) => return Some(Err(err)),
+                };
+                match self.tokens.load(deps.storage, &token_id) {
+                    Ok(token) if token.owner == "not_yet_set" => None,
+                    Ok(_) => Some(
+                        self.rarity
+                            .load(deps.storage, &token_id)
+                            .map(|rarity| RankedToken { token_id, rarity }),
+                    ),
+                    Err(err) => Some(Err(err)),
+                }
+            })
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        Ok(RarityRankingResponse { method, tokens })
+    }
+
+    fn query_token_history(
+        &self,
+        deps: Deps,
+        token_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<TokenHistoryResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive_int);
+
+        let history = self
+            .token_history
+            .prefix(&token_id)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, record)| record))
+            .collect::<StdResult<_>>()?;
+        Ok(TokenHistoryResponse { history })
+    }
+
+    fn query_transfer_hooks(&self, deps: Deps) -> StdResult<TransferHooksResponse> {
+        let hooks = self
+            .transfer_hooks
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                item.and_then(|(k, failure)| Ok((Addr::unchecked(String::from_utf8(k)?), failure)))
+            })
+            .collect::<StdResult<_>>()?;
+        Ok(TransferHooksResponse { hooks })
+    }
+
+    fn query_user_of(&self, deps: Deps, env: Env, token_id: String) -> StdResult<UserOfResponse> {
+        let user = self
+            .token_users
+            .may_load(deps.storage, &token_id)?
+            .filter(|user| !user.expires.is_expired(&env.block));
+        Ok(UserOfResponse { token_id, user })
+    }
+
+    fn query_tokens_used_by(
+        &self,
+        deps: Deps,
+        env: Env,
+        user: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let user_addr = deps.api.addr_validate(&user)?;
+        let tokens = self
+            .token_users
+            .idx
+            .user
+            .prefix(user_addr)
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter(|item| match item {
+                Ok((_, user)) => !user.expires.is_expired(&env.block),
+                Err(_) => true,
+            })
+            .take(limit)
+            .map(|item| item.and_then(|(k, _)| Ok(String::from_utf8(k)?)))
+            .collect::<StdResult<_>>()?;
+        Ok(TokensResponse { tokens })
+    }
+
+    fn query_rental_listing(
+        &self,
+        deps: Deps,
+        token_id: String,
+    ) -> StdResult<RentalListingResponse> {
+        let listing = self.rental_listings.may_load(deps.storage, &token_id)?;
+        Ok(RentalListingResponse { listing })
+    }
+
+    fn query_rental(&self, deps: Deps, env: Env, token_id: String) -> StdResult<RentalResponse> {
+        let rental = self.rentals.may_load(deps.storage, &token_id)?;
+        let claimable = match &rental {
+            Some(rental) => rental.earned(env.block.time) - rental.paid,
+            None => Uint128::zero(),
+        };
+        Ok(RentalResponse { rental, claimable })
+    }
+
+    fn query_transfer_lock(&self, deps: Deps, token_id: String) -> StdResult<TransferLockResponse> {
+        let lock = self.token_locks.may_load(deps.storage, &token_id)?;
+        Ok(TransferLockResponse { token_id, lock })
+    }
+
+    fn query_series_locks(&self, deps: Deps) -> StdResult<SeriesLocksResponse> {
+        let series = self
+            .series_locks
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.and_then(|(k, lock)| Ok((String::from_utf8(k)?, lock))))
+            .collect::<StdResult<_>>()?;
+        Ok(SeriesLocksResponse { series })
+    }
+
+    fn query_staking_config(&self, deps: Deps) -> StdResult<StakingConfigResponse> {
+        let config = self.staking_config.may_load(deps.storage)?;
+        Ok(StakingConfigResponse { config })
+    }
+
+    fn query_stake(&self, deps: Deps, env: Env, token_id: String) -> StdResult<StakeResponse> {
+        let stake = self.stakes.load(deps.storage, &token_id)?;
+        Ok(StakeResponse {
+            token_id,
+            pending: stake.pending(env.block.time),
+            stake,
+        })
+    }
+
+    fn query_stakes_by_owner(
+        &self,
+        deps: Deps,
+        env: Env,
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<StakesResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let owner_addr = deps.api.addr_validate(&owner)?;
+        let stakes: Vec<StakeResponse> = self
+            .stakes
+            .idx
+            .owner
+            .prefix(owner_addr)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.and_then(|(k, stake)| {
+                    Ok(StakeResponse {
+                        token_id: String::from_utf8(k)?,
+                        pending: stake.pending(env.block.time),
+                        stake,
+                    })
+                })
+            })
+            .collect::<StdResult<_>>()?;
+        let pending = stakes.iter().map(|stake| stake.pending).sum();
+        Ok(StakesResponse { stakes, pending })
+    }
+
+    fn query_staking_points(
+        &self,
+        deps: Deps,
+        address: String,
+    ) -> StdResult<StakingPointsResponse> {
+        let address = deps.api.addr_validate(&address)?;
+        let points = self
+            .staking_points
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+        Ok(StakingPointsResponse { points })
+    }
+
     // fn query_is_on_reveal(&self, deps: Deps) -> StdResult<IsOnRevealResponse> {
     //     let res: bool = self
     //         .is_on_reveal
@@ -107,10 +1059,8 @@ impl<'a> Cw721ExtendedQuery<Extension> for Cw721ExtendedContract<'a> {
     }
 
     fn query_get_balance(&self, deps: Deps, owner: String) -> StdResult<GetBalanceResponse> {
-        let res = self
-            .wallet_balance
-            .may_load(deps.storage, &Addr::unchecked(owner))?
-            .unwrap_or(0);
+        let owner = deps.api.addr_validate(&owner)?;
+        let res = self.holders.may_load(deps.storage, &owner)?.unwrap_or(0);
         Ok(GetBalanceResponse { balance: res })
     }
 
@@ -327,3 +1277,43 @@ impl<'a> Cw721ExtendedQuery<Extension> for Cw721ExtendedContract<'a> {
         Ok(VoterListResponse { voters })
     }
 }
+
+fn is_expired_listing(block: &BlockInfo, item: &StdResult<(Vec<u8>, Listing)>) -> bool {
+    match item {
+        Ok((_, listing)) => listing.expires.is_expired(block),
+        Err(_) => false,
+    }
+}
+
+fn is_expired_offer(block: &BlockInfo, item: &StdResult<(Vec<u8>, Offer)>) -> bool {
+    match item {
+        Ok((_, offer)) => offer.expires.is_expired(block),
+        Err(_) => false,
+    }
+}
+
+fn parse_offer(item: StdResult<(Vec<u8>, Offer)>) -> StdResult<OfferResponse> {
+    item.and_then(|(k, offer)| {
+        Ok(OfferResponse {
+            offer_id: parse_u64_key(&k)?,
+            offer,
+        })
+    })
+}
+
+fn parse_u64_key(k: &[u8]) -> StdResult<u64> {
+    let id: [u8; 8] = k
+        .try_into()
+        .map_err(|_| StdError::generic_err("Invalid u64 key"))?;
+    Ok(u64::from_be_bytes(id))
+}
+
+fn parse_swap(block: &BlockInfo, item: StdResult<(Vec<u8>, Swap)>) -> StdResult<SwapResponse> {
+    item.and_then(|(k, swap)| {
+        Ok(SwapResponse {
+            swap_id: parse_u64_key(&k)?,
+            expired: swap.expires.is_expired(block),
+            swap,
+        })
+    })
+}
@@ -1,13 +1,20 @@
+use crate::constants::{DEFAULT_HISTORY_RETENTION, MAX_TOKENS};
+use crate::events::TokenAction;
 use crate::state2::*;
 use schemars::JsonSchema;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
 use std::marker::PhantomData;
 
-use cosmwasm_std::{Addr, BlockInfo, Empty, StdResult, Storage, Timestamp};
+use cosmwasm_std::{
+    Addr, BlockInfo, Empty, Env, Order, StdError, StdResult, Storage, Timestamp, Uint128,
+};
 
 use cw721::{ContractInfoResponse, CustomMsg, Cw721, Expiration};
-use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_storage_plus::{
+    Index, IndexList, IndexedMap, Item, Map, MultiIndex, SnapshotMap, Strategy, U128Key, U64Key,
+};
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
 pub struct Trait {
@@ -45,13 +52,84 @@ where
     pub token_count: Item<'a, u64>,
     /// Stored as (granter, operator) giving operator full control over granter's account
     pub operators: Map<'a, (&'a Addr, &'a Addr), Expiration>,
+    /// `operators` keyed (operator, owner), kept in sync through save_operator / remove_operator
+    pub operator_grants: Map<'a, (&'a Addr, &'a Addr), Expiration>,
     pub tokens: IndexedMap<'a, &'a str, TokenInfo<T>, TokenIndexes<'a, T>>,
     // pub is_on_reveal: Item<'a, bool>,
     // pub cw3_signature: Map<'a, &'a Addr, bool>,
     // pub whitelist: Map<'a, &'a Addr, bool>,
     // pub freemint_count: Item<'a, u64>,
+    /// Mints made by each sender for the wallet limit, transfers and burns leave it as is
     pub wallet_balance: Map<'a, &'a Addr, u64>,
     pub time_deployed: Item<'a, Timestamp>,
+    pub dutch_auction: Item<'a, DutchAuction>,
+    /// Price paid by the latest dutch auction mint, the clearing price once sold out
+    pub dutch_auction_last_price: Item<'a, Uint128>,
+    /// Id of the latest dutch auction, payments and rebates are kept per auction
+    pub dutch_auction_id: Item<'a, u64>,
+    pub dutch_auction_payments: Map<'a, (U64Key, &'a Addr), AuctionPayment>,
+    /// Clearing price of a replaced or removed auction, its rebates stay claimable
+    pub dutch_auction_final_price: Map<'a, U64Key, Uint128>,
+    pub voucher_signer: Item<'a, SignerKey>,
+    /// Mints used per voucher nonce
+    pub voucher_nonces: Map<'a, U64Key, u64>,
+    /// next permit nonce per owner
+    pub permit_nonces: Map<'a, &'a Addr, u64>,
+    pub referral_config: Item<'a, ReferralConfig>,
+    pub sale_config: Item<'a, SaleConfig>,
+    pub block_mints: Item<'a, BlockMints>,
+    pub mint_activity: Map<'a, &'a Addr, MintActivity>,
+    pub listings: IndexedMap<'a, &'a str, Listing, ListingIndexes<'a>>,
+    pub auction_count: Item<'a, u64>,
+    pub auctions: IndexedMap<'a, U64Key, Auction, AuctionIndexes<'a>>,
+    pub offer_count: Item<'a, u64>,
+    pub offers: IndexedMap<'a, U64Key, Offer, OfferIndexes<'a>>,
+    pub swap_count: Item<'a, u64>,
+    pub swaps: IndexedMap<'a, U64Key, Swap, SwapIndexes<'a>>,
+    /// swaps listing each token, offered or requested
+    pub swap_tokens: Map<'a, (&'a str, U64Key), Empty>,
+    pub staking_config: Item<'a, StakingConfig>,
+    pub stakes: IndexedMap<'a, &'a str, StakeInfo, StakeIndexes<'a>>,
+    pub staking_points: Map<'a, &'a Addr, Uint128>,
+    pub token_locks: Map<'a, &'a str, TransferLock>,
+    pub token_users: IndexedMap<'a, &'a str, TokenUser, TokenUserIndexes<'a>>,
+    pub rental_listings: Map<'a, &'a str, RentalListing>,
+    pub rentals: Map<'a, &'a str, Rental>,
+    pub operator_filter: Item<'a, OperatorFilter>,
+    pub operator_registry: Map<'a, &'a Addr, OperatorStatus>,
+    pub transfer_hooks: Map<'a, &'a Addr, HookFailure>,
+    pub history_retention: Item<'a, u32>,
+    pub history_bounds: Map<'a, &'a str, HistoryBounds>,
+    pub token_history: Map<'a, (&'a str, U64Key), OwnershipRecord>,
+    /// (trait_type, value, token_id) of minted tokens, unrevealed metadata is not indexed
+    pub trait_tokens: Map<'a, (&'a str, &'a str, &'a str), Empty>,
+    pub trait_counts: Map<'a, (&'a str, &'a str), u64>,
+    pub metadata_rules: Item<'a, MetadataRules>,
+    /// set once the upload is finalized, no metadata can be added afterwards
+    pub rarity_method: Item<'a, RarityMethod>,
+    pub rarity: Map<'a, &'a str, Rarity>,
+    /// token id by rank
+    pub rarity_ranking: Map<'a, U64Key, String>,
+    /// trait counts over the finalized upload, written into a token's attributes at mint
+    pub upload_trait_counts: Map<'a, (&'a str, &'a str), u64>,
+    pub upload_supply: Item<'a, u64>,
+    /// Mirror of token owners answering queries at snapshot heights
+    pub owner_snapshots: SnapshotMap<'a, &'a str, Addr>,
+    pub snapshots: Map<'a, U64Key, Empty>,
+    pub snapshot_interval: Item<'a, u64>,
+    /// Tokens held per address, an entry only while the address holds one
+    pub holders: Map<'a, &'a Addr, u64>,
+    pub holder_count: Item<'a, u64>,
+    pub burned_count: Item<'a, u64>,
+    /// Mint payments kept by the contract, net of change and rebates
+    pub mint_revenue: Item<'a, Uint128>,
+    /// Funds held for users per denom, e.g. auction bids, which Withdraw leaves alone
+    pub escrow: Map<'a, &'a str, Uint128>,
+    pub series_locks: Map<'a, &'a str, SeriesLock>,
+    pub referrals: IndexedMap<'a, &'a Addr, ReferralStats, ReferralIndexes<'a>>,
+    /// Mints referred during a rebate dutch auction, per referrer and auction,
+    /// their commission is earned on the clearing price once it is known
+    pub auction_referrals: Map<'a, (&'a Addr, U64Key), u64>,
     #[allow(non_snake_case)]
     pub CONFIG: Item<'a, Config>,
     #[allow(non_snake_case)]
@@ -109,6 +187,30 @@ where
         let indexes = TokenIndexes {
             owner: MultiIndex::new(token_owner_idx, tokens_key, tokens_owner_key),
         };
+        let listing_indexes = ListingIndexes {
+            seller: MultiIndex::new(listing_seller_idx, "listings", "listings__seller"),
+            price: MultiIndex::new(listing_price_idx, "listings", "listings__price"),
+        };
+        let auction_indexes = AuctionIndexes {
+            state: MultiIndex::new(auction_state_idx, "auctions", "auctions__state"),
+        };
+        let offer_indexes = OfferIndexes {
+            bidder: MultiIndex::new(offer_bidder_idx, "offers", "offers__bidder"),
+            target: MultiIndex::new(offer_target_idx, "offers", "offers__target"),
+        };
+        let swap_indexes = SwapIndexes {
+            proposer: MultiIndex::new(swap_proposer_idx, "swaps", "swaps__proposer"),
+            counterparty: MultiIndex::new(swap_counterparty_idx, "swaps", "swaps__counterparty"),
+        };
+        let stake_indexes = StakeIndexes {
+            owner: MultiIndex::new(stake_owner_idx, "stakes", "stakes__owner"),
+        };
+        let token_user_indexes = TokenUserIndexes {
+            user: MultiIndex::new(token_user_idx, "token_users", "token_users__user"),
+        };
+        let referral_indexes = ReferralIndexes {
+            referrals: MultiIndex::new(referral_count_idx, "referrals", "referrals__count"),
+        };
         Self {
             contract_info: Item::new(contract_key),
             minter: Item::new(minter_key),
@@ -116,12 +218,70 @@ where
             // whitelist: Map::new("whitelist"),
             token_count: Item::new(token_count_key),
             operators: Map::new(operator_key),
+            operator_grants: Map::new("operator_grants"),
             tokens: IndexedMap::new(tokens_key, indexes),
             _custom_response: PhantomData,
             // is_on_reveal: Item::new("is_on_reveal"),
             // freemint_count: Item::new("freemint_count"),
             wallet_balance: Map::new("wallet_balance"),
             time_deployed: Item::new("time_deployed"),
+            dutch_auction: Item::new("dutch_auction"),
+            dutch_auction_last_price: Item::new("dutch_auction_last_price"),
+            dutch_auction_id: Item::new("dutch_auction_id"),
+            dutch_auction_payments: Map::new("dutch_auction_payments"),
+            dutch_auction_final_price: Map::new("dutch_auction_final_price"),
+            voucher_signer: Item::new("voucher_signer"),
+            voucher_nonces: Map::new("voucher_nonces"),
+            permit_nonces: Map::new("permit_nonces"),
+            referral_config: Item::new("referral_config"),
+            sale_config: Item::new("sale_config"),
+            block_mints: Item::new("block_mints"),
+            mint_activity: Map::new("mint_activity"),
+            listings: IndexedMap::new("listings", listing_indexes),
+            auction_count: Item::new("auction_count"),
+            auctions: IndexedMap::new("auctions", auction_indexes),
+            offer_count: Item::new("offer_count"),
+            offers: IndexedMap::new("offers", offer_indexes),
+            swap_count: Item::new("swap_count"),
+            swaps: IndexedMap::new("swaps", swap_indexes),
+            swap_tokens: Map::new("swap_tokens"),
+            staking_config: Item::new("staking_config"),
+            stakes: IndexedMap::new("stakes", stake_indexes),
+            staking_points: Map::new("staking_points"),
+            token_locks: Map::new("token_locks"),
+            token_users: IndexedMap::new("token_users", token_user_indexes),
+            rental_listings: Map::new("rental_listings"),
+            rentals: Map::new("rentals"),
+            operator_filter: Item::new("operator_filter"),
+            operator_registry: Map::new("operator_registry"),
+            transfer_hooks: Map::new("transfer_hooks"),
+            history_retention: Item::new("history_retention"),
+            history_bounds: Map::new("history_bounds"),
+            token_history: Map::new("token_history"),
+            trait_tokens: Map::new("trait_tokens"),
+            trait_counts: Map::new("trait_counts"),
+            metadata_rules: Item::new("metadata_rules"),
+            rarity_method: Item::new("rarity_method"),
+            rarity: Map::new("rarity"),
+            rarity_ranking: Map::new("rarity_ranking"),
+            upload_trait_counts: Map::new("upload_trait_counts"),
+            upload_supply: Item::new("upload_supply"),
+            owner_snapshots: SnapshotMap::new(
+                "owner_snapshots",
+                "owner_snapshots__checkpoints",
+                "owner_snapshots__changelog",
+                Strategy::Selected,
+            ),
+            snapshots: Map::new("snapshots"),
+            snapshot_interval: Item::new("snapshot_interval"),
+            holders: Map::new("holders"),
+            holder_count: Item::new("holder_count"),
+            burned_count: Item::new("burned_count"),
+            mint_revenue: Item::new("mint_revenue"),
+            escrow: Map::new("escrow"),
+            series_locks: Map::new("series_locks"),
+            referrals: IndexedMap::new("referrals", referral_indexes),
+            auction_referrals: Map::new("auction_referrals"),
             CONFIG: Item::new("config"),
             PROPOSAL_COUNT: Item::new("proposal_count"),
             BALLOTS: Map::new("votes"),
@@ -140,6 +300,304 @@ where
         Ok(val)
     }
 
+    /// Final dutch auction price: the last price paid once sold out, or the floor price
+    /// once it has been reached. None while the auction is still running.
+    pub fn dutch_auction_clearing_price(
+        &self,
+        storage: &dyn Storage,
+        auction: &DutchAuction,
+        time: Timestamp,
+    ) -> StdResult<Option<Uint128>> {
+        if self.token_count(storage)? >= MAX_TOKENS {
+            return self.dutch_auction_last_price.may_load(storage);
+        }
+        if auction.is_started(time) && auction.next_price_drop(time).is_none() {
+            return Ok(Some(auction.floor_price));
+        }
+        Ok(None)
+    }
+
+    /// Clearing price of the given dutch auction, None while it is still running
+    pub fn dutch_auction_clearing_price_of(
+        &self,
+        storage: &dyn Storage,
+        auction_id: u64,
+        time: Timestamp,
+    ) -> StdResult<Option<Uint128>> {
+        if Some(auction_id) == self.dutch_auction_id.may_load(storage)? {
+            if let Some(auction) = self.dutch_auction.may_load(storage)? {
+                return self.dutch_auction_clearing_price(storage, &auction, time);
+            }
+        }
+        self.dutch_auction_final_price
+            .may_load(storage, U64Key::new(auction_id))
+    }
+
+    /// Referral stats crediting the commission of referred rebate auction mints
+    /// whose clearing price is known, with the auctions settled that way
+    pub fn settled_referral_stats(
+        &self,
+        storage: &dyn Storage,
+        referrer: &Addr,
+        time: Timestamp,
+    ) -> StdResult<(ReferralStats, Vec<u64>)> {
+        let mut stats = self
+            .referrals
+            .may_load(storage, referrer)?
+            .unwrap_or_default();
+        let pending = self
+            .auction_referrals
+            .prefix(referrer)
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        if pending.is_empty() {
+            return Ok((stats, vec![]));
+        }
+
+        let config = self.referral_config.may_load(storage)?.unwrap_or_default();
+        let mut settled = vec![];
+        for (key, count) in pending {
+            let auction_id = u64::from_be_bytes(
+                key.as_slice()
+                    .try_into()
+                    .map_err(|_| StdError::generic_err("Invalid u64 key"))?,
+            );
+            if let Some(price) = self.dutch_auction_clearing_price_of(storage, auction_id, time)? {
+                // each mint is capped on its own, as when the commission is paid at mint
+                for _ in 0..count {
+                    stats.earned += config.commission(price, stats.earned);
+                    stats.volume += price;
+                }
+                settled.push(auction_id);
+            }
+        }
+        Ok((stats, settled))
+    }
+
+    /// Appends an ownership change to the token history, dropping the oldest
+    /// records beyond the retention limit, and updates the holder index and
+    /// the owner snapshots
+    pub fn record_ownership(
+        &self,
+        store: &mut dyn Storage,
+        env: &Env,
+        token_id: &str,
+        from: Option<&Addr>,
+        to: Option<&Addr>,
+        action: TokenAction,
+    ) -> StdResult<()> {
+        let retention = self
+            .history_retention
+            .may_load(store)?
+            .unwrap_or(DEFAULT_HISTORY_RETENTION) as u64;
+        let mut bounds = self
+            .history_bounds
+            .may_load(store, token_id)?
+            .unwrap_or_default();
+
+        let record = OwnershipRecord {
+            seq: bounds.next,
+            from: from.cloned(),
+            to: to.cloned(),
+            height: env.block.height,
+            time: env.block.time,
+            action,
+        };
+        self.token_history
+            .save(store, (token_id, U64Key::new(bounds.next)), &record)?;
+        bounds.next += 1;
+        while bounds.next - bounds.first > retention {
+            self.token_history
+                .remove(store, (token_id, U64Key::new(bounds.first)));
+            bounds.first += 1;
+        }
+        self.history_bounds.save(store, token_id, &bounds)?;
+        // the contract only escrows tokens, e.g. in auctions, it is not a holder
+        let is_holder = |addr: &&Addr| **addr != env.contract.address;
+        self.move_holding(store, from.filter(is_holder), to.filter(is_holder))?;
+
+        // a scheduled snapshot is taken before the first change past its interval,
+        // it records the owners at the end of the previous block
+        if let Some(interval) = self.snapshot_interval.may_load(store)? {
+            let height = env.block.height.saturating_sub(1);
+            let last = self
+                .snapshots
+                .keys(store, None, None, Order::Descending)
+                .next()
+                .map(|k| u64::from_be_bytes(k.try_into().unwrap_or_default()));
+            let due = match last {
+                Some(last) => height >= last + interval,
+                None => true,
+            };
+            if due {
+                self.take_snapshot(store, height)?;
+            }
+        }
+        match to {
+            Some(to) => self
+                .owner_snapshots
+                .save(store, token_id, to, env.block.height),
+            None => self
+                .owner_snapshots
+                .remove(store, token_id, env.block.height),
+        }
+    }
+
+    pub fn save_operator(
+        &self,
+        store: &mut dyn Storage,
+        owner: &Addr,
+        operator: &Addr,
+        expires: &Expiration,
+    ) -> StdResult<()> {
+        self.operators.save(store, (owner, operator), expires)?;
+        self.operator_grants.save(store, (operator, owner), expires)
+    }
+
+    pub fn remove_operator(&self, store: &mut dyn Storage, owner: &Addr, operator: &Addr) {
+        self.operators.remove(store, (owner, operator));
+        self.operator_grants.remove(store, (operator, owner));
+    }
+
+    fn move_holding(
+        &self,
+        store: &mut dyn Storage,
+        from: Option<&Addr>,
+        to: Option<&Addr>,
+    ) -> StdResult<()> {
+        let mut holder_count = self.holder_count.may_load(store)?.unwrap_or_default();
+        if let Some(from) = from {
+            match self.holders.may_load(store, from)?.unwrap_or_default() {
+                0 | 1 => {
+                    self.holders.remove(store, from);
+                    holder_count = holder_count.saturating_sub(1);
+                }
+                held => self.holders.save(store, from, &(held - 1))?,
+            }
+        }
+        if let Some(to) = to {
+            let held = self.holders.may_load(store, to)?.unwrap_or_default();
+            if held == 0 {
+                holder_count += 1;
+            }
+            self.holders.save(store, to, &(held + 1))?;
+        }
+        self.holder_count.save(store, &holder_count)
+    }
+
+    /// Owners at the end of block `height` can be queried from then on, so
+    /// changes made later in the same block are part of the snapshot.
+    /// The checkpoint is the start of the next block.
+    pub fn take_snapshot(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
+        self.owner_snapshots.add_checkpoint(store, height + 1)?;
+        self.snapshots.save(store, U64Key::new(height), &Empty {})
+    }
+
+    /// Errors with not found unless a snapshot was taken at `height`
+    pub fn assert_snapshot(&self, store: &dyn Storage, height: u64) -> StdResult<()> {
+        match self.snapshots.may_load(store, U64Key::new(height))? {
+            Some(_) => Ok(()),
+            None => Err(StdError::not_found("snapshot")),
+        }
+    }
+
+    /// Owner of a token at a snapshot height, None if it was not minted yet or burned
+    pub fn owner_at_height(
+        &self,
+        store: &dyn Storage,
+        token_id: &str,
+        height: u64,
+    ) -> StdResult<Option<Addr>> {
+        self.assert_snapshot(store, height)?;
+        self.owner_snapshots
+            .may_load_at_height(store, token_id, height + 1)
+    }
+
+    pub fn add_escrow(
+        &self,
+        store: &mut dyn Storage,
+        denom: &str,
+        amount: Uint128,
+    ) -> StdResult<()> {
+        let held = self.escrow.may_load(store, denom)?.unwrap_or_default();
+        self.escrow.save(store, denom, &(held + amount))
+    }
+
+    pub fn release_escrow(
+        &self,
+        store: &mut dyn Storage,
+        denom: &str,
+        amount: Uint128,
+    ) -> StdResult<()> {
+        let held = self.escrow.may_load(store, denom)?.unwrap_or_default();
+        match held.checked_sub(amount)? {
+            rest if rest.is_zero() => self.escrow.remove(store, denom),
+            rest => self.escrow.save(store, denom, &rest)?,
+        }
+        Ok(())
+    }
+
+    pub fn next_auction_id(&self, store: &mut dyn Storage) -> StdResult<u64> {
+        let id: u64 = self.auction_count.may_load(store)?.unwrap_or_default() + 1;
+        self.auction_count.save(store, &id)?;
+        Ok(id)
+    }
+
+    pub fn next_offer_id(&self, store: &mut dyn Storage) -> StdResult<u64> {
+        let id: u64 = self.offer_count.may_load(store)?.unwrap_or_default() + 1;
+        self.offer_count.save(store, &id)?;
+        Ok(id)
+    }
+
+    pub fn next_swap_id(&self, store: &mut dyn Storage) -> StdResult<u64> {
+        let id: u64 = self.swap_count.may_load(store)?.unwrap_or_default() + 1;
+        self.swap_count.save(store, &id)?;
+        Ok(id)
+    }
+
+    /// Saves a new swap, escrowing its offered funds, and indexes it under every token it lists
+    pub fn save_swap(&self, store: &mut dyn Storage, id: u64, swap: &Swap) -> StdResult<()> {
+        self.swaps.save(store, U64Key::new(id), swap)?;
+        for coin in swap.offered_funds.iter() {
+            self.add_escrow(store, &coin.denom, coin.amount)?;
+        }
+        for token_id in swap.offered_tokens.iter().chain(&swap.requested_tokens) {
+            self.swap_tokens
+                .save(store, (token_id, U64Key::new(id)), &Empty {})?;
+        }
+        Ok(())
+    }
+
+    /// Removes a swap, its offered funds leave the escrow to be paid out
+    pub fn remove_swap(&self, store: &mut dyn Storage, id: u64, swap: &Swap) -> StdResult<()> {
+        self.swaps.remove(store, U64Key::new(id))?;
+        for coin in swap.offered_funds.iter() {
+            self.release_escrow(store, &coin.denom, coin.amount)?;
+        }
+        for token_id in swap.offered_tokens.iter().chain(&swap.requested_tokens) {
+            self.swap_tokens.remove(store, (token_id, U64Key::new(id)));
+        }
+        Ok(())
+    }
+
+    /// Lock of the first series covering `token_num`
+    pub fn series_lock_for(
+        &self,
+        storage: &dyn Storage,
+        token_num: u64,
+    ) -> StdResult<Option<TransferLock>> {
+        for item in self
+            .series_locks
+            .range(storage, None, None, Order::Ascending)
+        {
+            let (_, series) = item?;
+            if series.contains(token_num) {
+                return Ok(Some(series.lock));
+            }
+        }
+        Ok(None)
+    }
+
     pub fn next_id(&self, store: &mut dyn Storage) -> StdResult<u64> {
         let id: u64 = self.PROPOSAL_COUNT.may_load(store)?.unwrap_or_default() + 1;
         self.PROPOSAL_COUNT.save(store, &id)?;
@@ -200,3 +658,189 @@ where
 pub fn token_owner_idx<T>(d: &TokenInfo<T>, k: Vec<u8>) -> (Addr, Vec<u8>) {
     (d.owner.clone(), k)
 }
+
+pub struct ReferralIndexes<'a> {
+    // pk goes to second tuple element
+    pub referrals: MultiIndex<'a, (U64Key, Vec<u8>), ReferralStats>,
+}
+
+impl<'a> IndexList<ReferralStats> for ReferralIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<ReferralStats>> + '_> {
+        let v: Vec<&dyn Index<ReferralStats>> = vec![&self.referrals];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn referral_count_idx(d: &ReferralStats, k: Vec<u8>) -> (U64Key, Vec<u8>) {
+    (U64Key::new(d.referrals), k)
+}
+
+pub struct ListingIndexes<'a> {
+    // pk goes to last tuple element
+    pub seller: MultiIndex<'a, (Addr, Vec<u8>), Listing>,
+    pub price: MultiIndex<'a, (Vec<u8>, U128Key, Vec<u8>), Listing>,
+}
+
+impl<'a> IndexList<Listing> for ListingIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Listing>> + '_> {
+        let v: Vec<&dyn Index<Listing>> = vec![&self.seller, &self.price];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn listing_seller_idx(d: &Listing, k: Vec<u8>) -> (Addr, Vec<u8>) {
+    (d.seller.clone(), k)
+}
+
+pub fn listing_price_idx(d: &Listing, k: Vec<u8>) -> (Vec<u8>, U128Key, Vec<u8>) {
+    (d.denom.as_bytes().to_vec(), U128Key::new(d.price.u128()), k)
+}
+
+pub struct AuctionIndexes<'a> {
+    // pk goes to second tuple element
+    pub state: MultiIndex<'a, (Vec<u8>, Vec<u8>), Auction>,
+}
+
+impl<'a> IndexList<Auction> for AuctionIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Auction>> + '_> {
+        let v: Vec<&dyn Index<Auction>> = vec![&self.state];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn auction_state_idx(d: &Auction, k: Vec<u8>) -> (Vec<u8>, Vec<u8>) {
+    (vec![d.state as u8], k)
+}
+
+pub struct OfferIndexes<'a> {
+    // pk goes to second tuple element
+    pub bidder: MultiIndex<'a, (Addr, Vec<u8>), Offer>,
+    pub target: MultiIndex<'a, (Vec<u8>, Vec<u8>), Offer>,
+}
+
+impl<'a> IndexList<Offer> for OfferIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Offer>> + '_> {
+        let v: Vec<&dyn Index<Offer>> = vec![&self.bidder, &self.target];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn offer_bidder_idx(d: &Offer, k: Vec<u8>) -> (Addr, Vec<u8>) {
+    (d.bidder.clone(), k)
+}
+
+pub fn offer_target_idx(d: &Offer, k: Vec<u8>) -> (Vec<u8>, Vec<u8>) {
+    (d.target.key(), k)
+}
+
+pub struct SwapIndexes<'a> {
+    // pk goes to second tuple element
+    pub proposer: MultiIndex<'a, (Addr, Vec<u8>), Swap>,
+    pub counterparty: MultiIndex<'a, (Addr, Vec<u8>), Swap>,
+}
+
+impl<'a> IndexList<Swap> for SwapIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Swap>> + '_> {
+        let v: Vec<&dyn Index<Swap>> = vec![&self.proposer, &self.counterparty];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn swap_proposer_idx(d: &Swap, k: Vec<u8>) -> (Addr, Vec<u8>) {
+    (d.proposer.clone(), k)
+}
+
+pub fn swap_counterparty_idx(d: &Swap, k: Vec<u8>) -> (Addr, Vec<u8>) {
+    (d.counterparty.clone(), k)
+}
+
+pub struct StakeIndexes<'a> {
+    // pk goes to second tuple element
+    pub owner: MultiIndex<'a, (Addr, Vec<u8>), StakeInfo>,
+}
+
+impl<'a> IndexList<StakeInfo> for StakeIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<StakeInfo>> + '_> {
+        let v: Vec<&dyn Index<StakeInfo>> = vec![&self.owner];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn stake_owner_idx(d: &StakeInfo, k: Vec<u8>) -> (Addr, Vec<u8>) {
+    (d.owner.clone(), k)
+}
+
+pub struct TokenUserIndexes<'a> {
+    // pk goes to second tuple element
+    pub user: MultiIndex<'a, (Addr, Vec<u8>), TokenUser>,
+}
+
+impl<'a> IndexList<TokenUser> for TokenUserIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<TokenUser>> + '_> {
+        let v: Vec<&dyn Index<TokenUser>> = vec![&self.user];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn token_user_idx(d: &TokenUser, k: Vec<u8>) -> (Addr, Vec<u8>) {
+    (d.user.clone(), k)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::{mock_env, MockStorage};
+
+    fn history_seqs(contract: &Cw721ExtendedContract, store: &dyn Storage) -> Vec<u64> {
+        contract
+            .token_history
+            .prefix("1")
+            .range(store, None, None, Order::Ascending)
+            .map(|item| item.unwrap().1.seq)
+            .collect()
+    }
+
+    #[test]
+    fn history_trimming() {
+        let contract = Cw721ExtendedContract::default();
+        let mut store = MockStorage::new();
+        let env = mock_env();
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+        contract.history_retention.save(&mut store, &3).unwrap();
+
+        contract
+            .record_ownership(&mut store, &env, "1", None, Some(&alice), TokenAction::Mint)
+            .unwrap();
+        for i in 0..4 {
+            let (from, to) = if i % 2 == 0 {
+                (&alice, &bob)
+            } else {
+                (&bob, &alice)
+            };
+            contract
+                .record_ownership(
+                    &mut store,
+                    &env,
+                    "1",
+                    Some(from),
+                    Some(to),
+                    TokenAction::Transfer,
+                )
+                .unwrap();
+        }
+        assert_eq!(history_seqs(&contract, &store), vec![2, 3, 4]);
+        assert_eq!(
+            contract.history_bounds.load(&store, "1").unwrap(),
+            HistoryBounds { first: 2, next: 5 }
+        );
+
+        // a lower retention trims the history on the next record
+        contract.history_retention.save(&mut store, &1).unwrap();
+        contract
+            .record_ownership(&mut store, &env, "1", Some(&alice), None, TokenAction::Burn)
+            .unwrap();
+        assert_eq!(history_seqs(&contract, &store), vec![5]);
+        assert_eq!(contract.holders.may_load(&store, &alice).unwrap(), None);
+    }
+}
@@ -1,9 +1,17 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{BlockInfo, CosmosMsg, Decimal, Empty, Uint128};
+use bech32::{ToBase32, Variant};
+use cosmwasm_std::{
+    to_vec, Addr, Api, Binary, BlockInfo, Coin, CosmosMsg, Decimal, Empty, StdError, StdResult,
+    Timestamp, Uint128,
+};
+use ripemd160::Ripemd160;
+use sha2::{Digest, Sha256};
 
+use crate::events::TokenAction;
 use crate::msg::Status;
+use crate::state::Trait;
 use crate::threshold::Threshold;
 use cw0::{Duration, Expiration};
 use cw3::Vote;
@@ -177,11 +185,863 @@ pub struct Ballot {
     pub vote: Vote,
 }
 
+/// Descending-price sale stage. The price starts at `start_price` and drops by
+/// `price_decrement` every `time_step` seconds until it reaches `floor_price`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DutchAuction {
+    pub start_time: Timestamp,
+    pub start_price: Uint128,
+    pub floor_price: Uint128,
+    pub price_decrement: Uint128,
+    /// length of one price step, in seconds
+    pub time_step: u64,
+    /// if set, every buyer can claim back the difference to the final clearing price
+    pub rebate: bool,
+}
+
+impl DutchAuction {
+    pub fn is_valid(&self) -> bool {
+        self.time_step > 0 && self.start_price >= self.floor_price
+    }
+
+    pub fn is_started(&self, time: Timestamp) -> bool {
+        time >= self.start_time
+    }
+
+    /// price charged for a mint at the given time
+    pub fn price_at(&self, time: Timestamp) -> Uint128 {
+        if !self.is_started(time) {
+            return self.start_price;
+        }
+        let steps = (time.seconds() - self.start_time.seconds()) / self.time_step;
+        let discount = self
+            .price_decrement
+            .checked_mul(Uint128::from(steps))
+            .unwrap_or(Uint128::MAX);
+        self.start_price
+            .checked_sub(discount)
+            .unwrap_or_default()
+            .max(self.floor_price)
+    }
+
+    /// time of the next price drop, None once the floor price is reached
+    pub fn next_price_drop(&self, time: Timestamp) -> Option<Timestamp> {
+        if self.price_at(time) <= self.floor_price || self.price_decrement.is_zero() {
+            return None;
+        }
+        if !self.is_started(time) {
+            return Some(self.start_time.plus_seconds(self.time_step));
+        }
+        let steps = (time.seconds() - self.start_time.seconds()) / self.time_step + 1;
+        Some(self.start_time.plus_seconds(steps * self.time_step))
+    }
+}
+
+/// What a single buyer paid during the dutch auction, used for rebates
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct AuctionPayment {
+    pub count: u64,
+    pub paid: Uint128,
+}
+
+/// Public key of the off-chain signer issuing mint vouchers
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerKey {
+    /// Compressed or uncompressed secp256k1 key, signs the sha256 of the voucher message
+    Secp256k1(Binary),
+    /// Ed25519 key, signs the voucher message itself
+    Ed25519(Binary),
+}
+
+impl SignerKey {
+    /// returns false on a bad signature, errors only on malformed keys or signatures
+    pub fn verify(&self, api: &dyn Api, message: &[u8], signature: &[u8]) -> StdResult<bool> {
+        let verified = match self {
+            SignerKey::Secp256k1(pubkey) => {
+                let hash = Sha256::digest(message);
+                api.secp256k1_verify(&hash, signature, pubkey)
+            }
+            SignerKey::Ed25519(pubkey) => api.ed25519_verify(message, signature, pubkey),
+        };
+        verified.map_err(|err| StdError::generic_err(err.to_string()))
+    }
+}
+
+/// Approval signed off-chain by a token owner with the key of their account
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ApprovalPermit {
+    pub owner: String,
+    /// Compressed secp256k1 key of the owner account, signs the sha256 of the permit message
+    pub pubkey: Binary,
+    pub spender: String,
+    /// None approves the spender as operator of all the owner's tokens
+    pub token_id: Option<String>,
+    /// the approval expires then, and the permit cannot be submitted afterwards
+    pub expires: Expiration,
+    /// must match the owner's permit nonce, which is bumped on every use
+    pub nonce: u64,
+}
+
+impl ApprovalPermit {
+    /// Like `MintVoucher::message`, prefixed with the chain id so a permit
+    /// cannot be replayed on another chain
+    pub fn message(&self, chain_id: &str, contract: &Addr) -> StdResult<Vec<u8>> {
+        let mut message = chain_id.as_bytes().to_vec();
+        message.extend(contract.as_bytes());
+        message.extend(to_vec(self)?);
+        Ok(message)
+    }
+
+    /// Account address of `pubkey`, bech32 encoded with the prefix of `owner`
+    pub fn signer(&self) -> StdResult<String> {
+        let (prefix, _, _) =
+            bech32::decode(&self.owner).map_err(|err| StdError::generic_err(err.to_string()))?;
+        let hash = Ripemd160::digest(&Sha256::digest(self.pubkey.as_slice()));
+        bech32::encode(&prefix, hash.to_base32(), Variant::Bech32)
+            .map_err(|err| StdError::generic_err(err.to_string()))
+    }
+}
+
+/// Mint authorization signed off-chain, e.g. after captcha or KYC
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MintVoucher {
+    pub recipient: String,
+    /// number of mints this voucher allows
+    pub quantity: u64,
+    pub price: Uint128,
+    pub nonce: u64,
+    pub expiry: Expiration,
+}
+
+impl MintVoucher {
+    /// The bytes the signer signs: the contract address followed by the JSON encoded
+    /// voucher, so a voucher cannot be replayed against another contract.
+    pub fn message(&self, contract: &Addr) -> StdResult<Vec<u8>> {
+        let mut message = contract.as_bytes().to_vec();
+        message.extend(to_vec(self)?);
+        Ok(message)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct ReferralConfig {
+    pub enabled: bool,
+    /// commission on the mint price, the clearing price of a rebate auction, in basis points
+    pub commission_bps: u64,
+    /// maximum total commission a single referrer can earn
+    pub max_commission: Option<Uint128>,
+}
+
+impl ReferralConfig {
+    pub fn is_valid(&self) -> bool {
+        self.commission_bps <= 10_000
+    }
+
+    /// commission for a mint at `price`, given what the referrer already earned
+    pub fn commission(&self, price: Uint128, earned: Uint128) -> Uint128 {
+        let commission = price.multiply_ratio(self.commission_bps, 10_000u64);
+        match self.max_commission {
+            Some(max) => commission.min(max.saturating_sub(earned)),
+            None => commission,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct ReferralStats {
+    /// number of mints referred
+    pub referrals: u64,
+    /// total mint price of the referred mints
+    pub volume: Uint128,
+    pub earned: Uint128,
+    pub claimed: Uint128,
+}
+
+impl ReferralStats {
+    pub fn claimable(&self) -> Uint128 {
+        self.earned.saturating_sub(self.claimed)
+    }
+}
+
+/// Anti-bot protections applied on mint, each one is off when unset
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct SaleConfig {
+    /// reject mints relayed through other contracts
+    pub reject_contract_senders: bool,
+    /// maximum mints per address in a single block
+    pub max_mints_per_address_per_block: Option<u64>,
+    /// maximum mints in a single block, for all addresses
+    pub max_mints_per_block: Option<u64>,
+    /// seconds an address has to wait between two mints
+    pub mint_cooldown: Option<u64>,
+}
+
+/// Number of mints in the block at `height`
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct BlockMints {
+    pub height: u64,
+    pub count: u64,
+}
+
+impl BlockMints {
+    /// mints already done in the given block
+    pub fn count_at(&self, height: u64) -> u64 {
+        if self.height == height {
+            self.count
+        } else {
+            0
+        }
+    }
+
+    pub fn increment(&mut self, height: u64) {
+        self.count = self.count_at(height) + 1;
+        self.height = height;
+    }
+}
+
+/// Recent mint activity of a single address
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct MintActivity {
+    pub block: BlockMints,
+    pub last_mint: Option<Timestamp>,
+}
+
+/// Fixed price marketplace listing
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Listing {
+    pub token_id: String,
+    /// owner of the token when it was listed, receives the sale proceeds
+    pub seller: Addr,
+    pub price: Uint128,
+    pub denom: String,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "lowercase")]
+#[repr(u8)]
+pub enum AuctionState {
+    /// accepting bids until `end_time`, waiting for settlement afterwards
+    Open = 1,
+    /// token and funds were distributed
+    Settled = 2,
+    /// cancelled by the seller before any bid
+    Cancelled = 3,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Bid {
+    pub bidder: Addr,
+    pub amount: Uint128,
+}
+
+/// English auction of a single token, escrowed by the contract
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Auction {
+    pub token_id: String,
+    pub seller: Addr,
+    pub denom: String,
+    pub reserve_price: Uint128,
+    pub min_increment: Uint128,
+    pub end_time: Timestamp,
+    /// bids placed within this many seconds of the end push the end back by as much
+    pub extension: u64,
+    pub highest_bid: Option<Bid>,
+    pub state: AuctionState,
+}
+
+impl Auction {
+    pub fn is_ended(&self, time: Timestamp) -> bool {
+        time >= self.end_time
+    }
+
+    /// lowest amount the next bid has to offer
+    pub fn min_bid(&self) -> Uint128 {
+        match &self.highest_bid {
+            Some(bid) => bid.amount + self.min_increment,
+            None => self.reserve_price,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum OfferTarget {
+    /// a specific token
+    Token { token_id: String },
+    /// any token of the collection
+    Collection {},
+    /// any token carrying this trait
+    Trait { trait_type: String, value: String },
+}
+
+impl OfferTarget {
+    /// unambiguous index key for the target
+    pub fn key(&self) -> Vec<u8> {
+        match self {
+            OfferTarget::Token { token_id } => [&[0u8][..], token_id.as_bytes()].concat(),
+            OfferTarget::Collection {} => vec![1u8],
+            OfferTarget::Trait { trait_type, value } => [
+                &[2u8][..],
+                &(trait_type.len() as u16).to_be_bytes(),
+                trait_type.as_bytes(),
+                value.as_bytes(),
+            ]
+            .concat(),
+        }
+    }
+}
+
+/// Escrowed offer on a token, or a collection-wide bid
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Offer {
+    pub bidder: Addr,
+    pub target: OfferTarget,
+    pub amount: Uint128,
+    pub denom: String,
+    pub expires: Expiration,
+}
+
+/// Atomic exchange proposed by `proposer` to `counterparty`
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Swap {
+    pub proposer: Addr,
+    pub offered_tokens: Vec<String>,
+    /// escrowed by the contract until the swap is accepted or cancelled
+    pub offered_funds: Vec<Coin>,
+    pub counterparty: Addr,
+    pub requested_tokens: Vec<String>,
+    /// paid by the counterparty on acceptance
+    pub requested_funds: Vec<Coin>,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StakingReward {
+    /// rewards are credited as points kept by the contract
+    Points {},
+    /// rewards are paid out of the contract balance of a CW20 token
+    Cw20 { address: Addr },
+}
+
+/// Bonus applied to the reward rate of tokens carrying a trait
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TraitMultiplier {
+    pub trait_type: String,
+    pub value: String,
+    pub multiplier: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StakingConfig {
+    pub enabled: bool,
+    /// reward accrued per second by a staked token, before multipliers
+    pub reward_rate: Uint128,
+    pub reward: StakingReward,
+    pub multipliers: Vec<TraitMultiplier>,
+}
+
+impl StakingConfig {
+    /// reward rate of a token, every matching multiplier is applied in turn
+    pub fn rate_for(&self, attributes: &[Trait]) -> Uint128 {
+        self.multipliers
+            .iter()
+            .filter(|m| {
+                attributes
+                    .iter()
+                    .any(|t| t.trait_type == m.trait_type && t.value == m.value)
+            })
+            .fold(self.reward_rate, |rate, m| rate * m.multiplier)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StakeInfo {
+    pub owner: Addr,
+    pub staked_at: Timestamp,
+    pub last_claim: Timestamp,
+    /// rate fixed when the token was staked
+    pub rate: Uint128,
+}
+
+impl StakeInfo {
+    pub fn pending(&self, time: Timestamp) -> Uint128 {
+        let elapsed = time.seconds().saturating_sub(self.last_claim.seconds());
+        self.rate * Uint128::from(elapsed)
+    }
+}
+
+/// Keeps a token in the wallet it was minted to
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TransferLock {
+    /// whether the owner may still burn the token
+    pub burnable: bool,
+}
+
+/// Transfer lock given at mint to every token id in `first_token..=last_token`
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SeriesLock {
+    pub first_token: u64,
+    pub last_token: u64,
+    pub lock: TransferLock,
+}
+
+impl SeriesLock {
+    pub fn contains(&self, token_num: u64) -> bool {
+        self.first_token <= token_num && token_num <= self.last_token
+    }
+}
+
+/// Time-limited user of a token, separate from its owner (ERC-4907)
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TokenUser {
+    pub user: Addr,
+    pub expires: Expiration,
+    /// paid rentals cannot be revoked by the owner before they expire
+    pub rented: bool,
+}
+
+/// Standing offer to rent a token out for `duration` seconds
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RentalListing {
+    pub owner: Addr,
+    pub price: Uint128,
+    pub denom: String,
+    pub duration: u64,
+}
+
+/// Rent held in escrow while a rental runs, released to the owner as it is earned
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Rental {
+    pub owner: Addr,
+    pub renter: Addr,
+    pub price: Uint128,
+    pub denom: String,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    /// part of the price already paid out to the owner
+    pub paid: Uint128,
+}
+
+impl Rental {
+    /// Rent earned by the owner at `now`, pro rata of the elapsed time
+    pub fn earned(&self, now: Timestamp) -> Uint128 {
+        if now >= self.end {
+            return self.price;
+        }
+        let elapsed = now.seconds().saturating_sub(self.start.seconds());
+        self.price
+            .multiply_ratio(elapsed, self.end.seconds() - self.start.seconds())
+    }
+}
+
+/// How the operator registry is enforced on contract operators
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OperatorFilter {
+    #[default]
+    Disabled,
+    /// only allowed contracts can be operators
+    Allowlist,
+    /// any contract but blocked ones can be operators
+    Blocklist,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum OperatorStatus {
+    Allowed,
+    Blocked,
+}
+
+impl OperatorFilter {
+    pub fn permits(&self, status: Option<OperatorStatus>) -> bool {
+        match self {
+            OperatorFilter::Disabled => true,
+            OperatorFilter::Allowlist => status == Some(OperatorStatus::Allowed),
+            OperatorFilter::Blocklist => status != Some(OperatorStatus::Blocked),
+        }
+    }
+}
+
+/// What happens to a token movement when a transfer hook fails
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum HookFailure {
+    /// the whole transaction is reverted
+    Fail,
+    /// the error is recorded and the movement goes through
+    Ignore,
+}
+
+/// One ownership change of a token, `from` is None on mint and `to` on burn
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OwnershipRecord {
+    pub seq: u64,
+    pub from: Option<Addr>,
+    pub to: Option<Addr>,
+    pub height: u64,
+    pub time: Timestamp,
+    pub action: TokenAction,
+}
+
+/// Sequence numbers of the oldest kept and the next ownership record of a token
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, JsonSchema, Debug)]
+pub struct HistoryBounds {
+    pub first: u64,
+    pub next: u64,
+}
+
+/// Admin checks applied to uploaded metadata on top of the format checks
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct MetadataRules {
+    /// trait types every token must have
+    pub required_trait_types: Vec<String>,
+    /// longest accepted string field, in characters
+    pub max_string_length: Option<u32>,
+}
+
+/// How the trait frequencies of a token combine into its rarity score,
+/// a higher score is rarer
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RarityMethod {
+    /// sum over the token traits of supply / tokens having the trait
+    InverseFrequency,
+    /// `InverseFrequency` divided by the number of traits, so the
+    /// number of traits does not weigh on the score
+    AverageInverseFrequency,
+}
+
+impl RarityMethod {
+    /// `counts` holds, for each distinct trait of the token, the tokens having it
+    pub fn score(&self, supply: u64, counts: &[u64]) -> Decimal {
+        let divisor = match self {
+            RarityMethod::InverseFrequency => 1,
+            RarityMethod::AverageInverseFrequency => counts.len().max(1) as u64,
+        };
+        counts
+            .iter()
+            .filter(|count| **count > 0)
+            .fold(Decimal::zero(), |sum, count| {
+                sum + Decimal::from_ratio(supply, count * divisor)
+            })
+    }
+}
+
+/// Rank 1 is the rarest token
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Rarity {
+    pub score: Decimal,
+    pub rank: u64,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use cosmwasm_std::testing::mock_env;
 
+    #[test]
+    fn dutch_auction_price() {
+        let auction = DutchAuction {
+            start_time: Timestamp::from_seconds(1000),
+            start_price: Uint128::new(1000),
+            floor_price: Uint128::new(400),
+            price_decrement: Uint128::new(100),
+            time_step: 60,
+            rebate: false,
+        };
+        assert!(auction.is_valid());
+
+        // before start and during the first step
+        assert_eq!(
+            auction.price_at(Timestamp::from_seconds(500)),
+            Uint128::new(1000)
+        );
+        assert_eq!(
+            auction.price_at(Timestamp::from_seconds(1059)),
+            Uint128::new(1000)
+        );
+        assert_eq!(
+            auction.next_price_drop(Timestamp::from_seconds(1059)),
+            Some(Timestamp::from_seconds(1060))
+        );
+
+        // steps down
+        assert_eq!(
+            auction.price_at(Timestamp::from_seconds(1060)),
+            Uint128::new(900)
+        );
+        assert_eq!(
+            auction.price_at(Timestamp::from_seconds(1250)),
+            Uint128::new(600)
+        );
+        assert_eq!(
+            auction.next_price_drop(Timestamp::from_seconds(1250)),
+            Some(Timestamp::from_seconds(1300))
+        );
+
+        // never below floor
+        assert_eq!(
+            auction.price_at(Timestamp::from_seconds(1360)),
+            Uint128::new(400)
+        );
+        assert_eq!(
+            auction.price_at(Timestamp::from_seconds(99999)),
+            Uint128::new(400)
+        );
+        assert_eq!(auction.next_price_drop(Timestamp::from_seconds(1360)), None);
+    }
+
+    #[test]
+    fn voucher_message() {
+        let voucher = MintVoucher {
+            recipient: "buyer".to_string(),
+            quantity: 2,
+            price: Uint128::new(100000),
+            nonce: 7,
+            expiry: Expiration::AtHeight(12345),
+        };
+        let message = voucher.message(&Addr::unchecked("contract")).unwrap();
+        assert_eq!(
+            String::from_utf8(message).unwrap(),
+            r#"contract{"recipient":"buyer","quantity":2,"price":"100000","nonce":7,"expiry":{"at_height":12345}}"#
+        );
+    }
+
+    #[test]
+    fn permit_message() {
+        let permit = ApprovalPermit {
+            owner: "owner".to_string(),
+            pubkey: Binary::from(b"key"),
+            spender: "market".to_string(),
+            token_id: None,
+            expires: Expiration::AtHeight(12345),
+            nonce: 0,
+        };
+        let message = permit
+            .message("chain-1", &Addr::unchecked("contract"))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(message).unwrap(),
+            r#"chain-1contract{"owner":"owner","pubkey":"a2V5","spender":"market","token_id":null,"expires":{"at_height":12345},"nonce":0}"#
+        );
+    }
+
+    #[test]
+    fn permit_signer() {
+        let mut permit = ApprovalPermit {
+            owner: "cosmos1pkptre7fdkl6gfrzlesjjvhxhlc3r4gmmk8rs6".to_string(),
+            pubkey: Binary::from_base64("A08EGB7ro1ORuFhjOnZcSgwYlpe0DSFjVNUIkNNQxwKQ").unwrap(),
+            spender: "market".to_string(),
+            token_id: None,
+            expires: Expiration::Never {},
+            nonce: 0,
+        };
+        assert_eq!(permit.signer().unwrap(), permit.owner);
+
+        permit.owner = "owner".to_string();
+        permit.signer().unwrap_err();
+    }
+
+    #[test]
+    fn rental_earned() {
+        let rental = Rental {
+            owner: Addr::unchecked("owner"),
+            renter: Addr::unchecked("renter"),
+            price: Uint128::new(300),
+            denom: "uluna".to_string(),
+            start: Timestamp::from_seconds(1000),
+            end: Timestamp::from_seconds(1300),
+            paid: Uint128::zero(),
+        };
+        assert_eq!(
+            rental.earned(Timestamp::from_seconds(1000)),
+            Uint128::zero()
+        );
+        assert_eq!(
+            rental.earned(Timestamp::from_seconds(1100)),
+            Uint128::new(100)
+        );
+        assert_eq!(
+            rental.earned(Timestamp::from_seconds(1300)),
+            Uint128::new(300)
+        );
+        assert_eq!(
+            rental.earned(Timestamp::from_seconds(2000)),
+            Uint128::new(300)
+        );
+    }
+
+    #[test]
+    fn referral_commission() {
+        let mut config = ReferralConfig {
+            enabled: true,
+            commission_bps: 500,
+            max_commission: None,
+        };
+        assert!(config.is_valid());
+        assert_eq!(
+            config.commission(Uint128::new(100000), Uint128::zero()),
+            Uint128::new(5000)
+        );
+
+        // capped per referrer
+        config.max_commission = Some(Uint128::new(12000));
+        assert_eq!(
+            config.commission(Uint128::new(100000), Uint128::new(10000)),
+            Uint128::new(2000)
+        );
+        assert_eq!(
+            config.commission(Uint128::new(100000), Uint128::new(12000)),
+            Uint128::zero()
+        );
+
+        config.commission_bps = 10_001;
+        assert!(!config.is_valid());
+    }
+
+    #[test]
+    fn block_mints() {
+        let mut mints = BlockMints::default();
+        mints.increment(10);
+        mints.increment(10);
+        assert_eq!(mints.count_at(10), 2);
+        assert_eq!(mints.count_at(11), 0);
+
+        // a new block starts counting from zero
+        mints.increment(11);
+        assert_eq!(
+            mints,
+            BlockMints {
+                height: 11,
+                count: 1
+            }
+        );
+    }
+
+    #[test]
+    fn auction_min_bid() {
+        let mut auction = Auction {
+            token_id: "1".to_string(),
+            seller: Addr::unchecked("seller"),
+            denom: "uusd".to_string(),
+            reserve_price: Uint128::new(1000),
+            min_increment: Uint128::new(50),
+            end_time: Timestamp::from_seconds(2000),
+            extension: 300,
+            highest_bid: None,
+            state: AuctionState::Open,
+        };
+        assert_eq!(auction.min_bid(), Uint128::new(1000));
+
+        auction.highest_bid = Some(Bid {
+            bidder: Addr::unchecked("bidder"),
+            amount: Uint128::new(1200),
+        });
+        assert_eq!(auction.min_bid(), Uint128::new(1250));
+        assert!(!auction.is_ended(Timestamp::from_seconds(1999)));
+        assert!(auction.is_ended(Timestamp::from_seconds(2000)));
+    }
+
+    #[test]
+    fn offer_target_keys() {
+        let token = OfferTarget::Token {
+            token_id: "1".to_string(),
+        };
+        let trait_a = OfferTarget::Trait {
+            trait_type: "ab".to_string(),
+            value: "c".to_string(),
+        };
+        let trait_b = OfferTarget::Trait {
+            trait_type: "a".to_string(),
+            value: "bc".to_string(),
+        };
+        assert_eq!(token.key(), b"\x001".to_vec());
+        assert_eq!(OfferTarget::Collection {}.key(), vec![1u8]);
+        assert_ne!(trait_a.key(), trait_b.key());
+    }
+
+    #[test]
+    fn staking_rate() {
+        let attribute = |trait_type: &str, value: &str| Trait {
+            display_type: None,
+            trait_type: trait_type.to_string(),
+            value: value.to_string(),
+            path: String::new(),
+            count: String::new(),
+            frequency: String::new(),
+        };
+        let config = StakingConfig {
+            enabled: true,
+            reward_rate: Uint128::new(10),
+            reward: StakingReward::Points {},
+            multipliers: vec![
+                TraitMultiplier {
+                    trait_type: "eyes".to_string(),
+                    value: "laser".to_string(),
+                    multiplier: Decimal::percent(200),
+                },
+                TraitMultiplier {
+                    trait_type: "hat".to_string(),
+                    value: "crown".to_string(),
+                    multiplier: Decimal::percent(150),
+                },
+            ],
+        };
+        assert_eq!(config.rate_for(&[]), Uint128::new(10));
+        assert_eq!(
+            config.rate_for(&[attribute("eyes", "laser"), attribute("hat", "cap")]),
+            Uint128::new(20)
+        );
+        assert_eq!(
+            config.rate_for(&[attribute("eyes", "laser"), attribute("hat", "crown")]),
+            Uint128::new(30)
+        );
+
+        let stake = StakeInfo {
+            owner: Addr::unchecked("owner"),
+            staked_at: Timestamp::from_seconds(100),
+            last_claim: Timestamp::from_seconds(150),
+            rate: Uint128::new(20),
+        };
+        assert_eq!(
+            stake.pending(Timestamp::from_seconds(160)),
+            Uint128::new(200)
+        );
+        assert_eq!(stake.pending(Timestamp::from_seconds(140)), Uint128::zero());
+    }
+
+    #[test]
+    fn operator_filter() {
+        use OperatorStatus::*;
+        assert!(OperatorFilter::Disabled.permits(Some(Blocked)));
+        assert!(OperatorFilter::Allowlist.permits(Some(Allowed)));
+        assert!(!OperatorFilter::Allowlist.permits(None));
+        assert!(OperatorFilter::Blocklist.permits(None));
+        assert!(!OperatorFilter::Blocklist.permits(Some(Blocked)));
+    }
+
+    #[test]
+    fn rarity_score() {
+        // a trait held by 1 token in 100, another by 50
+        let counts = [1, 50];
+        assert_eq!(
+            RarityMethod::InverseFrequency.score(100, &counts),
+            Decimal::from_ratio(102u128, 1u128)
+        );
+        assert_eq!(
+            RarityMethod::AverageInverseFrequency.score(100, &counts),
+            Decimal::from_ratio(51u128, 1u128)
+        );
+        assert_eq!(
+            RarityMethod::AverageInverseFrequency.score(100, &[]),
+            Decimal::zero()
+        );
+    }
+
     #[test]
     fn count_votes() {
         let mut votes = Votes::yes(5);
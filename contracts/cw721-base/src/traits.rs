@@ -4,7 +4,8 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 use cosmwasm_std::{
-    BlockInfo, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult, Uint128,
+    Binary, BlockInfo, Coin, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Response,
+    StdResult, Uint128,
 };
 
 use crate::error::*;
@@ -12,6 +13,7 @@ use crate::msg::*;
 use crate::state2::*;
 use crate::threshold::ThresholdResponse;
 use cw3::Vote;
+use cw721::TokensResponse;
 
 // TODO: move this somewhere else... ideally cosmwasm-std
 pub trait CustomMsg: Clone + std::fmt::Debug + PartialEq + JsonSchema {}
@@ -36,6 +38,389 @@ where
         _info: MessageInfo,
     ) -> Result<Response, ContractError>;
 
+    fn execute_set_dutch_auction(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        auction: Option<DutchAuction>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_claim_rebate(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: Option<u64>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_set_voucher_signer(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        signer: Option<SignerKey>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_set_referral_config(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        config: ReferralConfig,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_claim_referral_commission(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_set_sale_config(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        config: SaleConfig,
+    ) -> Result<Response, ContractError>;
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_list_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        price: Uint128,
+        denom: String,
+        expires: Option<Expiration>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_cancel_listing(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_buy_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+    ) -> Result<Response, ContractError>;
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_create_auction(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        denom: String,
+        reserve_price: Uint128,
+        min_increment: Uint128,
+        duration: u64,
+        extension: u64,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_place_bid(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_settle_auction(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_cancel_auction(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_make_offer(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        target: OfferTarget,
+        denom: String,
+        expires: Option<Expiration>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_accept_offer(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        offer_id: u64,
+        token_id: String,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_cancel_offer(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        offer_id: u64,
+    ) -> Result<Response, ContractError>;
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_propose_swap(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        counterparty: String,
+        offered_tokens: Vec<String>,
+        requested_tokens: Vec<String>,
+        requested_funds: Vec<Coin>,
+        expires: Option<Expiration>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_accept_swap(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        swap_id: u64,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_cancel_swap(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        swap_id: u64,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_set_transfer_lock(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_ids: Vec<String>,
+        lock: Option<TransferLock>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_set_series_lock(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        series: String,
+        series_lock: Option<SeriesLock>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_burn(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_set_operator_filter(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        filter: OperatorFilter,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_set_operator_status(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        operators: Vec<String>,
+        status: Option<OperatorStatus>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_approve_with_permit(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        permit: ApprovalPermit,
+        signature: Binary,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_revoke_all_operators(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_prune_operator_grants(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        operator: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_take_snapshot(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_set_snapshot_interval(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        interval: Option<u64>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_set_history_retention(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        limit: u32,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_set_metadata_rules(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        rules: MetadataRules,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_finalize_upload(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        rarity_method: RarityMethod,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_set_transfer_hook(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        failure: Option<HookFailure>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_batch_transfer_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        recipient: String,
+        token_ids: Vec<String>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_batch_send_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        token_ids: Vec<String>,
+        msg: Binary,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_set_user(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        user: Option<String>,
+        expires: Option<Expiration>,
+    ) -> Result<Response, ContractError>;
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_list_for_rent(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+        price: Uint128,
+        denom: String,
+        duration: u64,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_cancel_rental_listing(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_rent(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_claim_rent(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_id: String,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_set_staking_config(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        config: StakingConfig,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_stake(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_ids: Vec<String>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_unstake(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_ids: Vec<String>,
+    ) -> Result<Response, ContractError>;
+
+    fn execute_claim_staking_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        token_ids: Vec<String>,
+    ) -> Result<Response, ContractError>;
+
     // fn execute_set_art_reveal(
     //     &self,
     //     _deps: DepsMut,
@@ -135,6 +520,232 @@ where
 
     fn check_royalties(&self, _deps: Deps) -> StdResult<CheckRoyaltiesResponse>;
 
+    fn query_dutch_auction(&self, deps: Deps, env: Env) -> StdResult<DutchAuctionResponse>;
+
+    fn query_voucher_signer(&self, deps: Deps) -> StdResult<VoucherSignerResponse>;
+
+    fn query_voucher_nonce(&self, deps: Deps, nonce: u64) -> StdResult<VoucherNonceResponse>;
+
+    fn query_referral_config(&self, deps: Deps) -> StdResult<ReferralConfig>;
+
+    fn query_referral_stats(
+        &self,
+        deps: Deps,
+        env: Env,
+        referrer: String,
+    ) -> StdResult<ReferralStatsResponse>;
+
+    fn query_sale_config(&self, deps: Deps) -> StdResult<SaleConfig>;
+
+    fn query_listing(&self, deps: Deps, token_id: String) -> StdResult<ListingResponse>;
+
+    fn query_listings_by_seller(
+        &self,
+        deps: Deps,
+        env: Env,
+        seller: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ListingsResponse>;
+
+    #[allow(clippy::too_many_arguments)]
+    fn query_listings_by_price(
+        &self,
+        deps: Deps,
+        env: Env,
+        denom: String,
+        min_price: Option<Uint128>,
+        max_price: Option<Uint128>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ListingsResponse>;
+
+    fn query_auction(&self, deps: Deps, env: Env, auction_id: u64) -> StdResult<AuctionResponse>;
+
+    fn query_auctions_by_state(
+        &self,
+        deps: Deps,
+        env: Env,
+        state: AuctionState,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<AuctionsResponse>;
+
+    fn query_offer(&self, deps: Deps, offer_id: u64) -> StdResult<OfferResponse>;
+
+    fn query_offers_by_target(
+        &self,
+        deps: Deps,
+        env: Env,
+        target: OfferTarget,
+        include_expired: bool,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<OffersResponse>;
+
+    fn query_offers_by_bidder(
+        &self,
+        deps: Deps,
+        env: Env,
+        bidder: String,
+        include_expired: bool,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<OffersResponse>;
+
+    fn query_operator_registry(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<OperatorRegistryResponse>;
+
+    fn query_permit_nonce(&self, deps: Deps, owner: String) -> StdResult<PermitNonceResponse>;
+
+    fn query_snapshots(
+        &self,
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<SnapshotsResponse>;
+
+    fn query_owner_of_at_height(
+        &self,
+        deps: Deps,
+        token_id: String,
+        height: u64,
+    ) -> StdResult<OwnerOfAtHeightResponse>;
+
+    fn query_tokens_of_at_height(
+        &self,
+        deps: Deps,
+        owner: String,
+        height: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensResponse>;
+
+    fn query_tokens_by_trait(
+        &self,
+        deps: Deps,
+        trait_type: String,
+        value: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensByTraitResponse>;
+
+    fn query_trait_values(
+        &self,
+        deps: Deps,
+        trait_type: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TraitValuesResponse>;
+
+    fn query_holders(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<HoldersResponse>;
+
+    fn query_collection_stats(&self, deps: Deps, env: Env) -> StdResult<CollectionStatsResponse>;
+
+    fn query_metadata_rules(&self, deps: Deps) -> StdResult<MetadataRulesResponse>;
+
+    fn query_rarity(&self, deps: Deps, token_id: String) -> StdResult<RarityResponse>;
+
+    fn query_rarity_ranking(
+        &self,
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<RarityRankingResponse>;
+
+    fn query_token_history(
+        &self,
+        deps: Deps,
+        token_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<TokenHistoryResponse>;
+
+    fn query_transfer_hooks(&self, deps: Deps) -> StdResult<TransferHooksResponse>;
+
+    fn query_user_of(&self, deps: Deps, env: Env, token_id: String) -> StdResult<UserOfResponse>;
+
+    fn query_tokens_used_by(
+        &self,
+        deps: Deps,
+        env: Env,
+        user: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensResponse>;
+
+    fn query_rental_listing(
+        &self,
+        deps: Deps,
+        token_id: String,
+    ) -> StdResult<RentalListingResponse>;
+
+    fn query_rental(&self, deps: Deps, env: Env, token_id: String) -> StdResult<RentalResponse>;
+
+    fn query_transfer_lock(&self, deps: Deps, token_id: String) -> StdResult<TransferLockResponse>;
+
+    fn query_series_locks(&self, deps: Deps) -> StdResult<SeriesLocksResponse>;
+
+    fn query_staking_config(&self, deps: Deps) -> StdResult<StakingConfigResponse>;
+
+    fn query_stake(&self, deps: Deps, env: Env, token_id: String) -> StdResult<StakeResponse>;
+
+    fn query_stakes_by_owner(
+        &self,
+        deps: Deps,
+        env: Env,
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<StakesResponse>;
+
+    fn query_staking_points(&self, deps: Deps, address: String)
+        -> StdResult<StakingPointsResponse>;
+
+    fn query_swap(&self, deps: Deps, env: Env, swap_id: u64) -> StdResult<SwapResponse>;
+
+    fn query_swaps_by_proposer(
+        &self,
+        deps: Deps,
+        env: Env,
+        proposer: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<SwapsResponse>;
+
+    fn query_swaps_by_counterparty(
+        &self,
+        deps: Deps,
+        env: Env,
+        counterparty: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<SwapsResponse>;
+
+    fn query_all_listings(
+        &self,
+        deps: Deps,
+        env: Env,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ListingsResponse>;
+
+    fn query_referral_leaderboard(
+        &self,
+        deps: Deps,
+        env: Env,
+        limit: Option<u32>,
+    ) -> StdResult<ReferralLeaderboardResponse>;
+
     // fn query_is_on_reveal(&self, _deps: Deps) -> StdResult<IsOnRevealResponse>;
 
     fn query_get_token_uri(&self, _deps: Deps, token_id: String) -> StdResult<GetTokenUriResponse>;
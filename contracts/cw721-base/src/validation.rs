@@ -0,0 +1,256 @@
+use cosmwasm_std::Decimal;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use crate::error::ContractError;
+use crate::state::{Metadata, Trait};
+use crate::state2::MetadataRules;
+
+/// Checks the format of every metadata field, then the admin rules
+pub fn validate_metadata(meta: &Metadata, rules: &MetadataRules) -> Result<(), ContractError> {
+    if let Some(name) = &meta.name {
+        check_not_empty("name", name)?;
+    }
+    check_uri("image", meta.image.as_deref(), &["ipfs://", "https://"])?;
+    check_uri(
+        "animation_url",
+        meta.animation_url.as_deref(),
+        &["ipfs://", "https://"],
+    )?;
+    check_uri("external_url", meta.external_url.as_deref(), &["https://"])?;
+    check_uri("youtube_url", meta.youtube_url.as_deref(), &["https://"])?;
+    if let Some(color) = &meta.background_color {
+        check_color(color)?;
+    }
+
+    let attributes = meta.attributes.as_deref().unwrap_or_default();
+    let mut trait_types = BTreeSet::new();
+    for attr in attributes.iter() {
+        check_trait(attr)?;
+        if !trait_types.insert(attr.trait_type.as_str()) {
+            return Err(ContractError::DuplicateTraitType {
+                trait_type: attr.trait_type.clone(),
+            });
+        }
+    }
+    if let Some(missing) = rules
+        .required_trait_types
+        .iter()
+        .find(|trait_type| !trait_types.contains(trait_type.as_str()))
+    {
+        return Err(ContractError::MissingTraitType {
+            trait_type: missing.clone(),
+        });
+    }
+    if let Some(max) = rules.max_string_length {
+        check_lengths(meta, max)?;
+    }
+    Ok(())
+}
+
+fn check_not_empty(field: &str, value: &str) -> Result<(), ContractError> {
+    if value.trim().is_empty() {
+        return Err(ContractError::EmptyMetadataField {
+            field: field.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// `uri` must use one of `schemes` and have a non empty path without whitespace
+fn check_uri(field: &str, uri: Option<&str>, schemes: &[&str]) -> Result<(), ContractError> {
+    let uri = match uri {
+        Some(uri) => uri,
+        None => return Ok(()),
+    };
+    let valid = schemes.iter().any(|scheme| match uri.strip_prefix(scheme) {
+        // the CID or the host
+        Some(rest) => !rest.split('/').next().unwrap_or_default().is_empty(),
+        None => false,
+    }) && !uri.chars().any(char::is_whitespace);
+    if !valid {
+        return Err(ContractError::InvalidMetadataUri {
+            field: field.to_string(),
+            uri: uri.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// a coin denom as the bank module accepts it: a letter, then 2 to 127
+/// letters, digits or `/:._-`
+pub fn is_valid_denom(denom: &str) -> bool {
+    let mut chars = denom.chars();
+    let first_ok = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic());
+    first_ok
+        && (3..=128).contains(&denom.len())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c))
+}
+
+/// six hexadecimal digits without a leading `#`, as marketplaces expect
+fn check_color(color: &str) -> Result<(), ContractError> {
+    if color.len() != 6 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ContractError::InvalidBackgroundColor {
+            color: color.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// `count` and `frequency` may be left empty, they are filled in at mint once the upload is finalized
+fn check_trait(attr: &Trait) -> Result<(), ContractError> {
+    check_not_empty("trait_type", &attr.trait_type)?;
+    check_not_empty("value", &attr.value)?;
+    if !attr.count.is_empty() && attr.count.parse::<u64>().is_err() {
+        return Err(ContractError::InvalidTraitNumber {
+            trait_type: attr.trait_type.clone(),
+            field: "count".to_string(),
+        });
+    }
+    if !attr.frequency.is_empty() && Decimal::from_str(&attr.frequency).is_err() {
+        return Err(ContractError::InvalidTraitNumber {
+            trait_type: attr.trait_type.clone(),
+            field: "frequency".to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn check_lengths(meta: &Metadata, max: u32) -> Result<(), ContractError> {
+    let mut fields = vec![
+        ("image", meta.image.as_deref()),
+        ("image_data", meta.image_data.as_deref()),
+        ("external_url", meta.external_url.as_deref()),
+        ("description", meta.description.as_deref()),
+        ("name", meta.name.as_deref()),
+        ("background_color", meta.background_color.as_deref()),
+        ("animation_url", meta.animation_url.as_deref()),
+        ("youtube_url", meta.youtube_url.as_deref()),
+    ];
+    for attr in meta.attributes.iter().flatten() {
+        fields.push(("display_type", attr.display_type.as_deref()));
+        fields.push(("trait_type", Some(attr.trait_type.as_str())));
+        fields.push(("value", Some(attr.value.as_str())));
+        fields.push(("path", Some(attr.path.as_str())));
+    }
+    match fields
+        .into_iter()
+        .find(|(_, value)| matches!(value, Some(value) if value.chars().count() > max as usize))
+    {
+        Some((field, _)) => Err(ContractError::MetadataFieldTooLong {
+            field: field.to_string(),
+            max,
+        }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn metadata() -> Metadata {
+        Metadata {
+            name: Some("Token #1".to_string()),
+            image: Some("ipfs://QmRiLKmhizpnwqpHGeiJnL4G6fsPAxdEdCiDkuJpt7xHPH/1.png".to_string()),
+            background_color: Some("1A2b3C".to_string()),
+            attributes: Some(vec![Trait {
+                trait_type: "eyes".to_string(),
+                value: "blue".to_string(),
+                count: "12".to_string(),
+                frequency: "0.12".to_string(),
+                ..Trait::default()
+            }]),
+            ..Metadata::default()
+        }
+    }
+
+    #[test]
+    fn metadata_format() {
+        let rules = MetadataRules::default();
+        validate_metadata(&metadata(), &rules).unwrap();
+
+        let mut meta = metadata();
+        meta.name = Some(" ".to_string());
+        assert_eq!(
+            validate_metadata(&meta, &rules).unwrap_err(),
+            ContractError::EmptyMetadataField {
+                field: "name".to_string()
+            }
+        );
+
+        for image in [
+            "ipfs://",
+            "https:///x.png",
+            "http://host/x.png",
+            "ipfs://Qm x",
+        ] {
+            let mut meta = metadata();
+            meta.image = Some(image.to_string());
+            assert_eq!(
+                validate_metadata(&meta, &rules).unwrap_err(),
+                ContractError::InvalidMetadataUri {
+                    field: "image".to_string(),
+                    uri: image.to_string()
+                }
+            );
+        }
+
+        let mut meta = metadata();
+        meta.background_color = Some("#ffffff".to_string());
+        assert_eq!(
+            validate_metadata(&meta, &rules).unwrap_err(),
+            ContractError::InvalidBackgroundColor {
+                color: "#ffffff".to_string()
+            }
+        );
+
+        let mut meta = metadata();
+        let mut attributes = meta.attributes.clone().unwrap();
+        attributes[0].frequency = "12%".to_string();
+        meta.attributes = Some(attributes.clone());
+        assert_eq!(
+            validate_metadata(&meta, &rules).unwrap_err(),
+            ContractError::InvalidTraitNumber {
+                trait_type: "eyes".to_string(),
+                field: "frequency".to_string()
+            }
+        );
+
+        attributes[0].frequency = String::new();
+        attributes.push(attributes[0].clone());
+        meta.attributes = Some(attributes);
+        assert_eq!(
+            validate_metadata(&meta, &rules).unwrap_err(),
+            ContractError::DuplicateTraitType {
+                trait_type: "eyes".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn metadata_rules() {
+        let rules = MetadataRules {
+            required_trait_types: vec!["eyes".to_string(), "mouth".to_string()],
+            max_string_length: None,
+        };
+        assert_eq!(
+            validate_metadata(&metadata(), &rules).unwrap_err(),
+            ContractError::MissingTraitType {
+                trait_type: "mouth".to_string()
+            }
+        );
+
+        let rules = MetadataRules {
+            required_trait_types: vec![],
+            max_string_length: Some(8),
+        };
+        assert_eq!(
+            validate_metadata(&metadata(), &rules).unwrap_err(),
+            ContractError::MetadataFieldTooLong {
+                field: "image".to_string(),
+                max: 8
+            }
+        );
+    }
+}